@@ -0,0 +1,21 @@
+//! Writes every screen to `<out dir>/<screen>.png`, scaled up for easier viewing.
+//!
+//! cargo run --example render_screens -- [out dir] [scale]
+
+use std::{env, error::Error, fs, path::PathBuf};
+
+use solana_display_core::simulator::render_screens;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let out_dir = PathBuf::from(args.next().unwrap_or_else(|| "screens".to_string()));
+    let scale = args.next().map(|s| s.parse()).transpose()?.unwrap_or(4);
+
+    fs::create_dir_all(&out_dir)?;
+    for (name, framebuffer) in render_screens() {
+        let path = out_dir.join(format!("{}.png", name));
+        fs::write(&path, framebuffer.to_png(scale)?)?;
+        println!("wrote {}", path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,491 @@
+//! The `alerts` setting: price and balance thresholds that take over the
+//! display when crossed.
+//!
+//! Written as a comma separated list of `<subject><op><threshold>` entries,
+//! e.g. `"price>250, price<120, balance<1.5, Hot<0.1"`. The subject is `price`
+//! (the SOL price in the quote currency), `balance` (SOL across every watched
+//! wallet) or the label of one wallet; the operator is `>` or `<`. An empty
+//! setting turns alerts off.
+//!
+//! With a vote account configured the validator going delinquent raises an
+//! alert as well, see [`Alert::delinquency`].
+//!
+//! A crossed threshold latches: the alert stays up until it is acknowledged
+//! with the button, and fires again only once the value went back across the
+//! threshold and crosses it anew.
+//!
+//! Like the playlist, [`check`] is a `const fn` so a malformed entry in
+//! `cfg.toml` fails the build.
+
+use core::fmt;
+use std::{error::Error, time::Duration};
+
+use crate::{
+    fetcher::Source,
+    format::lamports_to_sol,
+    playlist::{eq, find, trim},
+    state::Snapshot,
+    wallets::{label_index, Wallet},
+};
+
+/// How often the alert screen and LEDs change while an alert is up.
+pub const ALERT_BLINK: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertsError {
+    EmptyEntry,
+    MissingComparison,
+    UnknownSubject,
+    InvalidThreshold,
+}
+
+impl AlertsError {
+    pub const fn message(&self) -> &'static str {
+        match self {
+            AlertsError::EmptyEntry => "the alerts list has an empty entry (stray comma?)",
+            AlertsError::MissingComparison => {
+                "an alert needs a > or < between subject and threshold"
+            }
+            AlertsError::UnknownSubject => {
+                "an alert subject must be price, balance or the label of a wallet"
+            }
+            AlertsError::InvalidThreshold => "an alert threshold must be a decimal number",
+        }
+    }
+}
+
+impl fmt::Display for AlertsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl Error for AlertsError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subject {
+    Price,
+    /// Sum of every watched wallet.
+    TotalBalance,
+    /// The wallet at this index of the wallets setting.
+    Wallet(usize),
+    /// The configured validator, 1 while it is delinquent and 0 otherwise.
+    Delinquency,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Above,
+    Below,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub subject: Subject,
+    /// What the alert screen calls the subject: `SOL`, `Total` or the label.
+    pub name: String,
+    pub direction: Direction,
+    /// In the quote currency for the price, in SOL for balances.
+    pub threshold: f64,
+}
+
+impl Alert {
+    /// Fires when the configured validator becomes delinquent.
+    pub fn delinquency() -> Self {
+        Self {
+            subject: Subject::Delinquency,
+            name: "Validator".to_string(),
+            direction: Direction::Above,
+            threshold: 0.5,
+        }
+    }
+
+    pub fn source(&self) -> Source {
+        match self.subject {
+            Subject::Price => Source::Price,
+            Subject::TotalBalance | Subject::Wallet(_) => Source::Balance,
+            Subject::Delinquency => Source::Validator,
+        }
+    }
+
+    /// Latest value of the subject, `None` until it has been fetched.
+    pub fn value(&self, snapshot: &Snapshot) -> Option<f64> {
+        match self.subject {
+            Subject::Price => snapshot.price.value.map(|price| price.price),
+            Subject::TotalBalance => snapshot
+                .balances
+                .iter()
+                .map(|balance| balance.value)
+                .sum::<Option<u64>>()
+                .map(lamports_to_sol),
+            Subject::Wallet(index) => snapshot
+                .balances
+                .get(index)
+                .and_then(|balance| balance.value)
+                .map(lamports_to_sol),
+            Subject::Delinquency => {
+                snapshot
+                    .validator
+                    .value
+                    .as_ref()
+                    .map(|status| if status.delinquent { 1.0 } else { 0.0 })
+            }
+        }
+    }
+
+    pub fn is_crossed(&self, value: f64) -> bool {
+        match self.direction {
+            Direction::Above => value > self.threshold,
+            Direction::Below => value < self.threshold,
+        }
+    }
+
+    /// `SOL > $250.00`, `Hot < 0.10 SOL` for the alert screen.
+    fn describe(&self, value: f64, snapshot: &Snapshot) -> Triggered {
+        if self.subject == Subject::Delinquency {
+            let lag = snapshot
+                .validator
+                .value
+                .as_ref()
+                .map_or(0, |s| s.vote_lag());
+            return Triggered {
+                title: "Validator delinquent".to_string(),
+                value: format!("last vote {} slots ago", lag),
+            };
+        }
+        let op = match self.direction {
+            Direction::Above => '>',
+            Direction::Below => '<',
+        };
+        let amount = |amount: f64| match self.subject {
+            Subject::Price => snapshot.quote.format(amount),
+            _ => format!("{:.2} SOL", amount),
+        };
+        Triggered {
+            title: format!("{} {} {}", self.name, op, amount(self.threshold)),
+            value: format!("now {}", amount(value)),
+        }
+    }
+}
+
+/// A latched alert as shown on the alert screen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Triggered {
+    pub title: String,
+    pub value: String,
+}
+
+/// Watches the alerts against every new snapshot and latches the crossed ones.
+#[derive(Debug, Clone, Default)]
+pub struct AlertMonitor {
+    alerts: Vec<Alert>,
+    /// Whether each alert may fire, cleared when it fires and set again once
+    /// its value is back on the safe side of the threshold.
+    armed: Vec<bool>,
+    latched: Vec<Triggered>,
+}
+
+impl AlertMonitor {
+    pub fn new(alerts: Vec<Alert>) -> Self {
+        Self {
+            armed: vec![true; alerts.len()],
+            alerts,
+            latched: Vec::new(),
+        }
+    }
+
+    /// Sources the alerts need fetched even when no screen shows them.
+    pub fn sources(&self) -> Vec<Source> {
+        let mut sources = Vec::new();
+        for alert in &self.alerts {
+            if !sources.contains(&alert.source()) {
+                sources.push(alert.source());
+            }
+        }
+        sources
+    }
+
+    /// Latch every armed alert the snapshot crosses. Returns whether an
+    /// alert is up.
+    pub fn check(&mut self, snapshot: &Snapshot) -> bool {
+        for (alert, armed) in self.alerts.iter().zip(&mut self.armed) {
+            let Some(value) = alert.value(snapshot) else {
+                continue;
+            };
+            if !alert.is_crossed(value) {
+                *armed = true;
+            } else if *armed {
+                *armed = false;
+                println!("Alert: {} {}", alert.name, value);
+                self.latched.push(alert.describe(value, snapshot));
+            }
+        }
+        self.is_latched()
+    }
+
+    pub fn is_latched(&self) -> bool {
+        !self.latched.is_empty()
+    }
+
+    /// Alerts that fired since the last acknowledgement, oldest first.
+    pub fn latched(&self) -> &[Triggered] {
+        &self.latched
+    }
+
+    /// Clear the alert screen, called when the button is pressed.
+    pub fn acknowledge(&mut self) {
+        self.latched.clear();
+    }
+}
+
+/// LEDs on GPIO19, GPIO14 and GPIO15 at `step` of the alert pattern: the two
+/// outer LEDs and the middle one flash in turn.
+pub const fn alert_leds(step: u32) -> [bool; 3] {
+    match step % 2 {
+        0 => [true, false, true],
+        _ => [false, true, false],
+    }
+}
+
+/// `123`, `0.5`, `1.25`: digits with at most one decimal point.
+const fn is_decimal(bytes: &[u8]) -> bool {
+    let mut digits = 0;
+    let mut points = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'0'..=b'9' => digits += 1,
+            b'.' => points += 1,
+            _ => return false,
+        }
+        i += 1;
+    }
+    digits > 0 && points <= 1
+}
+
+/// `(subject, direction, threshold)` of one entry.
+const fn parse_entry(entry: &[u8]) -> Result<(&[u8], Direction, &[u8]), AlertsError> {
+    let entry = trim(entry);
+    if entry.is_empty() {
+        return Err(AlertsError::EmptyEntry);
+    }
+    let above = find(entry, b'>');
+    let below = find(entry, b'<');
+    let (at, direction) = if above < below {
+        (above, Direction::Above)
+    } else {
+        (below, Direction::Below)
+    };
+    let (subject, threshold) = match entry.split_at(at) {
+        (_, []) => return Err(AlertsError::MissingComparison),
+        (subject, [_, threshold @ ..]) => (trim(subject), trim(threshold)),
+    };
+    if !is_decimal(threshold) {
+        return Err(AlertsError::InvalidThreshold);
+    }
+    Ok((subject, direction, threshold))
+}
+
+/// `price`, `balance` or the index of the wallet with that label.
+const fn parse_subject(subject: &[u8], wallets: &str) -> Result<Subject, AlertsError> {
+    if eq(subject, b"price") {
+        return Ok(Subject::Price);
+    }
+    if eq(subject, b"balance") {
+        return Ok(Subject::TotalBalance);
+    }
+    match label_index(wallets, subject) {
+        Some(index) => Ok(Subject::Wallet(index)),
+        None => Err(AlertsError::UnknownSubject),
+    }
+}
+
+/// Validate the alerts against the wallets setting without allocating,
+/// usable in `const` context.
+pub const fn validate(alerts: &str, wallets: &str) -> Result<(), AlertsError> {
+    let mut rest = alerts.as_bytes();
+    if trim(rest).is_empty() {
+        return Ok(());
+    }
+    loop {
+        let (entry, tail) = rest.split_at(find(rest, b','));
+        match parse_entry(entry) {
+            Ok((subject, _, _)) => {
+                if let Err(err) = parse_subject(subject, wallets) {
+                    return Err(err);
+                }
+            }
+            Err(err) => return Err(err),
+        }
+        match tail {
+            [] => return Ok(()),
+            [_, tail @ ..] => rest = tail,
+        }
+    }
+}
+
+/// Panics with a readable message on invalid alerts. Meant for
+/// `const _: () = alerts::check(CONFIG.alerts, CONFIG.wallets);` so it fails
+/// the build.
+pub const fn check(alerts: &str, wallets: &str) {
+    if let Err(err) = validate(alerts, wallets) {
+        panic!("{}", err.message());
+    }
+}
+
+/// Parse the alerts, wallet subjects are looked up by label in `wallets`.
+pub fn parse(alerts: &str, wallets: &[Wallet]) -> Result<Vec<Alert>, AlertsError> {
+    if alerts.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    alerts
+        .split(',')
+        .map(|entry| {
+            let (subject, direction, threshold) = parse_entry(entry.as_bytes())?;
+            // every part is a sub-slice of a `&str` split on ASCII bytes
+            let text = |bytes| String::from_utf8_lossy(bytes).into_owned();
+            let (subject, name) = match subject {
+                b"price" => (Subject::Price, "SOL".to_string()),
+                b"balance" => (Subject::TotalBalance, "Total".to_string()),
+                label => {
+                    let label = text(label);
+                    let index = wallets
+                        .iter()
+                        .position(|wallet| wallet.label == label)
+                        .ok_or(AlertsError::UnknownSubject)?;
+                    (Subject::Wallet(index), label)
+                }
+            };
+            Ok(Alert {
+                subject,
+                name,
+                direction,
+                threshold: text(threshold)
+                    .parse()
+                    .map_err(|_| AlertsError::InvalidThreshold)?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::{sample_snapshot, sample_wallets, SAMPLE_WALLET};
+    use std::time::Instant;
+
+    const WALLETS: &str = "Treasury=aKgfWjGePnbFgDAuCqxB5oymuFxQskvCtrw6eYfDa7fg, Hot=9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM";
+
+    #[test]
+    fn parses_price_and_balance_thresholds() {
+        let alerts = parse("price>250, balance < 1.5, Hot<0.1", &sample_wallets()).unwrap();
+        assert_eq!(
+            alerts[0],
+            Alert {
+                subject: Subject::Price,
+                name: "SOL".to_string(),
+                direction: Direction::Above,
+                threshold: 250.0,
+            }
+        );
+        assert_eq!(alerts[1].subject, Subject::TotalBalance);
+        assert_eq!(alerts[1].direction, Direction::Below);
+        assert_eq!(alerts[2].subject, Subject::Wallet(1));
+        assert_eq!(alerts[2].threshold, 0.1);
+        assert_eq!(parse(" ", &sample_wallets()).unwrap(), []);
+    }
+
+    #[test]
+    fn rejects_malformed_alerts() {
+        assert_eq!(validate("", WALLETS), Ok(()));
+        assert_eq!(validate("Hot<0.5", WALLETS), Ok(()));
+        assert_eq!(validate("price>1,", WALLETS), Err(AlertsError::EmptyEntry));
+        assert_eq!(
+            validate("price=1", WALLETS),
+            Err(AlertsError::MissingComparison)
+        );
+        assert_eq!(
+            validate("Cold<1", WALLETS),
+            Err(AlertsError::UnknownSubject)
+        );
+        assert_eq!(
+            validate("price>1.2.3", WALLETS),
+            Err(AlertsError::InvalidThreshold)
+        );
+        assert_eq!(
+            validate("price<", WALLETS),
+            Err(AlertsError::InvalidThreshold)
+        );
+        let wallets = [Wallet::new("Wallet", SAMPLE_WALLET)];
+        assert_eq!(parse("Cold<1", &wallets), Err(AlertsError::UnknownSubject));
+    }
+
+    #[test]
+    fn alerts_latch_until_acknowledged_and_rearm() {
+        // the sample price is 187.42
+        let alerts = parse("price>200, Hot<5", &sample_wallets()).unwrap();
+        let mut monitor = AlertMonitor::new(alerts);
+        assert_eq!(monitor.sources(), [Source::Price, Source::Balance]);
+
+        let mut snapshot = sample_snapshot(Instant::now());
+        assert!(monitor.check(&snapshot));
+        assert_eq!(
+            monitor.latched(),
+            [Triggered {
+                title: "Hot < 5.00 SOL".to_string(),
+                value: "now 3.20 SOL".to_string(),
+            }]
+        );
+        monitor.acknowledge();
+        // still below 5 SOL, but already acknowledged
+        assert!(!monitor.check(&snapshot));
+
+        snapshot.price.value.as_mut().unwrap().price = 201.5;
+        assert!(monitor.check(&snapshot));
+        assert_eq!(monitor.latched()[0].title, "SOL > $200.00");
+        assert_eq!(monitor.latched()[0].value, "now $201.50");
+        monitor.acknowledge();
+
+        // back under and over again fires again
+        snapshot.price.value.as_mut().unwrap().price = 190.0;
+        assert!(!monitor.check(&snapshot));
+        snapshot.price.value.as_mut().unwrap().price = 205.0;
+        assert!(monitor.check(&snapshot));
+    }
+
+    #[test]
+    fn total_balance_needs_every_wallet() {
+        let alerts = parse("balance<100", &sample_wallets()).unwrap();
+        let mut snapshot = sample_snapshot(Instant::now());
+        assert!((alerts[0].value(&snapshot).unwrap() - 15.587778901).abs() < 1e-9);
+        snapshot.balances[2].value = None;
+        assert_eq!(alerts[0].value(&snapshot), None);
+    }
+
+    #[test]
+    fn delinquency_alert_follows_the_validator() {
+        let mut monitor = AlertMonitor::new(vec![Alert::delinquency()]);
+        assert_eq!(monitor.sources(), [Source::Validator]);
+        let mut snapshot = sample_snapshot(Instant::now());
+        assert!(!monitor.check(&snapshot));
+
+        let status = snapshot.validator.value.as_mut().unwrap();
+        status.delinquent = true;
+        status.slot += 150;
+        assert!(monitor.check(&snapshot));
+        assert_eq!(
+            monitor.latched(),
+            [Triggered {
+                title: "Validator delinquent".to_string(),
+                value: "last vote 153 slots ago".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn led_pattern_alternates() {
+        assert_eq!(alert_leds(0), [true, false, true]);
+        assert_eq!(alert_leds(1), [false, true, false]);
+        assert_eq!(alert_leds(2), alert_leds(0));
+    }
+}
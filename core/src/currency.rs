@@ -0,0 +1,165 @@
+//! Currency the prices are quoted in.
+//!
+//! The `quote_currency` setting takes a CoinGecko currency code such as
+//! `"usd"`, `"eur"`, `"cop"` or `"btc"`. Each currency knows how it is written
+//! on the display: its symbol and how many decimals are worth showing.
+
+use core::fmt;
+use std::error::Error;
+
+use crate::playlist::trim;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Currency {
+    /// Lowercase CoinGecko code, e.g. `"eur"`.
+    pub code: &'static str,
+    pub symbol: &'static str,
+    pub decimals: usize,
+    /// The symbol follows the amount, `0.002134 BTC`, instead of leading it.
+    pub symbol_after: bool,
+}
+
+const fn currency(code: &'static str, symbol: &'static str, decimals: usize) -> Currency {
+    Currency {
+        code,
+        symbol,
+        decimals,
+        symbol_after: false,
+    }
+}
+
+const fn crypto(code: &'static str, symbol: &'static str, decimals: usize) -> Currency {
+    Currency {
+        code,
+        symbol,
+        decimals,
+        symbol_after: true,
+    }
+}
+
+pub const USD: Currency = currency("usd", "$", 2);
+
+/// Every currency the setting accepts. Currencies whose amounts run into the
+/// thousands for a single SOL (COP, ARS, CLP, JPY) are shown without cents.
+pub const CURRENCIES: &[Currency] = &[
+    USD,
+    currency("eur", "€", 2),
+    currency("gbp", "£", 2),
+    currency("jpy", "¥", 0),
+    currency("cad", "$", 2),
+    currency("aud", "$", 2),
+    currency("chf", "Fr.", 2),
+    currency("brl", "R$", 2),
+    currency("mxn", "$", 2),
+    currency("cop", "$", 0),
+    currency("ars", "$", 0),
+    currency("clp", "$", 0),
+    crypto("btc", "BTC", 6),
+    crypto("eth", "ETH", 4),
+];
+
+impl Currency {
+    pub fn is_usd(&self) -> bool {
+        self.code == USD.code
+    }
+
+    /// Upper case code for labels, e.g. `EUR`.
+    pub fn label(&self) -> String {
+        self.code.to_ascii_uppercase()
+    }
+
+    /// `$187.42`, `€172.10`, `$781520`, `0.002134 BTC`.
+    pub fn format(&self, amount: f64) -> String {
+        let amount = format!("{:.*}", self.decimals, amount);
+        if self.symbol_after {
+            format!("{} {}", amount, self.symbol)
+        } else {
+            format!("{}{}", self.symbol, amount)
+        }
+    }
+}
+
+impl Default for Currency {
+    fn default() -> Self {
+        USD
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurrencyError {
+    UnknownCurrency,
+}
+
+impl CurrencyError {
+    pub const fn message(&self) -> &'static str {
+        match self {
+            CurrencyError::UnknownCurrency => {
+                "unknown quote currency, expected one of usd, eur, gbp, jpy, cad, aud, chf, brl, mxn, cop, ars, clp, btc or eth"
+            }
+        }
+    }
+}
+
+impl fmt::Display for CurrencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl Error for CurrencyError {}
+
+const fn eq_ignore_case(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if !a[i].eq_ignore_ascii_case(&b[i]) {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Look a currency up by its code, ignoring case and surrounding spaces.
+pub const fn find(setting: &str) -> Result<Currency, CurrencyError> {
+    let code = trim(setting.as_bytes());
+    let mut i = 0;
+    while i < CURRENCIES.len() {
+        if eq_ignore_case(code, CURRENCIES[i].code.as_bytes()) {
+            return Ok(CURRENCIES[i]);
+        }
+        i += 1;
+    }
+    Err(CurrencyError::UnknownCurrency)
+}
+
+/// Panics with a readable message on an unknown currency. Meant for
+/// `const _: () = currency::check(CONFIG.quote_currency);` so it fails the build.
+pub const fn check(setting: &str) {
+    if let Err(err) = find(setting) {
+        panic!("{}", err.message());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amounts_use_the_symbol_and_decimals_of_the_currency() {
+        assert_eq!(USD.format(187.42), "$187.42");
+        assert_eq!(find("EUR").unwrap().format(172.1), "€172.10");
+        assert_eq!(find(" cop ").unwrap().format(781_520.4), "$781520");
+        assert_eq!(find("btc").unwrap().format(0.0021344), "0.002134 BTC");
+        assert_eq!(find("btc").unwrap().label(), "BTC");
+    }
+
+    #[test]
+    fn unknown_codes_are_rejected() {
+        assert_eq!(find("usd"), Ok(USD));
+        assert_eq!(find("doge"), Err(CurrencyError::UnknownCurrency));
+        assert_eq!(find(""), Err(CurrencyError::UnknownCurrency));
+    }
+}
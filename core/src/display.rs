@@ -0,0 +1,840 @@
+use core::{fmt::Debug, time::Duration};
+use embedded_graphics::{
+    image::{Image, ImageRaw},
+    mono_font::{
+        iso_8859_15::{FONT_4X6, FONT_6X10},
+        MonoFont, MonoTextStyleBuilder,
+    },
+    pixelcolor::BinaryColor,
+    prelude::{DrawTarget, Point, Primitive, Size},
+    primitives::{Polyline, PrimitiveStyle, PrimitiveStyleBuilder, Rectangle, Triangle},
+    text::{Baseline, Text},
+    Drawable,
+};
+use qrcodegen::{QrCode, QrCodeEcc};
+use ssd1306::{
+    mode::BufferedGraphicsMode,
+    prelude::{DisplaySize, WriteOnlyDataCommand},
+    Ssd1306,
+};
+
+use crate::{
+    alerts::Triggered,
+    currency::Currency,
+    format::{
+        format_age, format_amount, format_countdown, format_eta, format_sol, format_utc_time,
+        lamports_to_sol, MISSING,
+    },
+    leader::LeaderSchedule,
+    portfolio::Portfolio,
+    production::ProductionHistory,
+    rewards::{RewardHistory, REWARDS_EPOCHS},
+    rpc::EpochInfo,
+    stake::StakeAccounts,
+    state::{Freshness, Performance, SolPrice},
+    tokens::TokenBalance,
+    validator::ValidatorStatus,
+};
+
+pub const DISPLAY_WIDTH: usize = 128;
+pub const DISPLAY_HEIGHT: usize = 64;
+
+/// Wallet rows that fit on the summary page above its total.
+pub const WALLET_ROWS: usize = 3;
+
+/// Token rows shown per page of the token list.
+pub const TOKEN_ROWS: usize = 4;
+
+/// Largest holdings listed under the portfolio total.
+pub const PORTFOLIO_ROWS: usize = 4;
+
+/// Closed epochs listed under the current block production.
+pub const PRODUCTION_ROWS: usize = 3;
+
+/// Alerts listed on the alert screen, its title counts all of them.
+pub const ALERT_ROWS: usize = 2;
+
+/// Pages the token list needs for `count` tokens, at least one.
+pub fn token_pages(count: usize) -> usize {
+    count.div_ceil(TOKEN_ROWS).max(1)
+}
+
+/// A monochrome draw target whose pixels only reach the panel on `flush`.
+pub trait Flush: DrawTarget<Color = BinaryColor> {
+    fn flush(&mut self) -> Result<(), Self::Error>;
+}
+
+impl<DI, SIZE> Flush for Ssd1306<DI, SIZE, BufferedGraphicsMode<SIZE>>
+where
+    DI: WriteOnlyDataCommand,
+    SIZE: DisplaySize,
+{
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ssd1306::flush(self)
+    }
+}
+
+/// x coordinate that centers `text` horizontally for a font `char_width` pixels wide.
+pub fn centered_x(text: &str, char_width: usize) -> u8 {
+    (DISPLAY_WIDTH.saturating_sub(text.chars().count() * char_width) / 2) as u8
+}
+
+pub struct DisplayModule<D> {
+    pub display: D,
+}
+
+impl<D> DisplayModule<D>
+where
+    D: Flush,
+    D::Error: Debug,
+{
+    pub fn new(mut display: D) -> Self {
+        let on = PrimitiveStyleBuilder::new()
+            .stroke_width(1)
+            .stroke_color(BinaryColor::On)
+            .build();
+
+        Rectangle::new(Point::new(0, 0), Size::new(127, 63))
+            .into_styled(on)
+            .draw(&mut display)
+            .unwrap();
+        Self { display }
+    }
+
+    pub fn create_centered_text(&mut self, text: &str, font: MonoFont) {
+        let text_height = font.character_size.height as usize;
+
+        let x = centered_x(text, font.character_size.width as usize);
+        let y = (DISPLAY_HEIGHT - text_height) / 2;
+
+        self.create_text(text, x, y as u8, font);
+    }
+
+    pub fn create_text(&mut self, text: &str, x_c: u8, y_c: u8, font: MonoFont) {
+        self.create_colored_text(text, x_c, y_c, font, BinaryColor::On);
+    }
+
+    /// Like [`DisplayModule::create_text`], `BinaryColor::Off` draws dark
+    /// text on a lit background.
+    pub fn create_colored_text(
+        &mut self,
+        text: &str,
+        x_c: u8,
+        y_c: u8,
+        font: MonoFont,
+        color: BinaryColor,
+    ) {
+        let text_style = MonoTextStyleBuilder::new()
+            .font(&font)
+            .text_color(color)
+            .build();
+
+        let display = &mut self.display;
+        Text::with_baseline(
+            text,
+            Point::new(x_c.into(), y_c.into()),
+            text_style,
+            Baseline::Top,
+        )
+        .draw(display)
+        .unwrap();
+        display.flush().unwrap();
+    }
+
+    pub fn create_black_rectangle(&mut self) {
+        let display = &mut self.display;
+        let on = PrimitiveStyleBuilder::new()
+            .stroke_width(1)
+            .stroke_color(BinaryColor::On)
+            .fill_color(BinaryColor::Off)
+            .build();
+
+        Rectangle::new(Point::new(0, 0), Size::new(127, 63))
+            .into_styled(on)
+            .draw(display)
+            .unwrap();
+    }
+
+    pub fn draw_image(&mut self) {
+        let display = &mut self.display;
+        let size = 32i32;
+        let raw: ImageRaw<BinaryColor> =
+            ImageRaw::new(include_bytes!("../sol_logo.raw"), size as u32);
+        let im = Image::new(&raw, Point::new((128 - size) / 2, (64 - size) / 2));
+        im.draw(display).unwrap();
+        display.flush().unwrap();
+    }
+
+    pub fn draw_qr_code(&mut self, data: &str) {
+        self.draw_qr_code_at(data, None);
+    }
+
+    /// QR code pushed against the right edge with `label` to its left.
+    pub fn draw_labeled_qr_code(&mut self, label: &str, data: &str) {
+        let right_x = self.draw_qr_code_at(data, Some(4));
+        let (font, char_width) = if label.len() * 6 + 8 <= right_x {
+            (FONT_6X10, 6)
+        } else {
+            (FONT_4X6, 4)
+        };
+        let x = right_x.saturating_sub(label.len() * char_width) / 2;
+        self.create_text(label, x as u8, 27, font);
+    }
+
+    /// Draw `data` as a QR code, centered or `right_margin` pixels from the
+    /// right edge. Returns the x where the code starts.
+    fn draw_qr_code_at(&mut self, data: &str, right_margin: Option<i32>) -> usize {
+        let display = &mut self.display;
+        let qr = QrCode::encode_text(data, QrCodeEcc::Low).unwrap();
+        let qr_size = qr.size();
+
+        let max_width = 128;
+        let max_height = 64;
+        let padding_y = 6;
+
+        let available_height = max_height - (padding_y * 2);
+        let scale = 2;
+
+        // scale the QR to be able to scan it
+        let qr_width = qr_size * scale;
+        let qr_height = qr_size * scale;
+
+        let offset_x = match right_margin {
+            Some(margin) => max_width - qr_width - margin,
+            None => (max_width - qr_width) / 2,
+        };
+        let offset_y = ((available_height - qr_height) / 2) + padding_y;
+
+        for y in 0..qr_size {
+            for x in 0..qr_size {
+                // this condition determines whether we need to draw a pixel or not.
+                if qr.get_module(x, y) {
+                    Rectangle::new(
+                        Point::new(offset_x + x * scale, offset_y + y * scale),
+                        Size::new(scale as u32, scale as u32),
+                    )
+                    .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                    .draw(display)
+                    .unwrap();
+                }
+            }
+        }
+        display.flush().unwrap(); // write the data
+        offset_x.max(0) as usize
+    }
+
+    pub fn draw_time(&mut self, data: (&str, &str)) {
+        let x = 5;
+        let y = 64 - 9;
+        let (time, date) = data;
+        self.create_text(date, x as u8, y, FONT_4X6);
+        let x_time = 128 - (time.len() * 4) - 5;
+        self.create_text(time, x_time as u8, y, FONT_4X6);
+    }
+
+    /// Mark a value as stale in the top right corner: `!` and its age.
+    pub fn draw_freshness(&mut self, freshness: Freshness) {
+        if let Freshness::Stale(age) = freshness {
+            let marker = format!("!{}", format_age(age));
+            let x = 128 - (marker.len() * 4) - 4;
+            self.create_text(&marker, x as u8, 3, FONT_4X6);
+        }
+    }
+
+    /// SOL balance of `wallet`, plus the SOL its liquid staking tokens are
+    /// worth when it holds any.
+    pub fn draw_balance(
+        &mut self,
+        wallet: &str,
+        lamports: Option<u64>,
+        lst_sol: Option<f64>,
+        time: (&str, &str),
+    ) {
+        self.create_black_rectangle();
+        let label = format!("{} SOL:", wallet);
+        let formatted = lamports.map_or(MISSING.to_string(), format_sol);
+
+        self.create_text(&label, centered_x(&label, 6), 16, FONT_6X10);
+        self.create_text(&formatted, centered_x(&formatted, 6), 33, FONT_6X10);
+        if let Some(sol) = lst_sol {
+            let staked = format!("+{:.2} SOL in LSTs", sol);
+            self.create_text(&staked, centered_x(&staked, 4), 45, FONT_4X6);
+        }
+        self.draw_time(time);
+    }
+
+    /// One `label  balance` row per wallet and their total. Only the first
+    /// [`WALLET_ROWS`] wallets get a row, the total always covers all of them.
+    pub fn draw_wallets(&mut self, balances: &[(&str, Option<u64>)], time: (&str, &str)) {
+        self.create_black_rectangle();
+        let total = balances
+            .iter()
+            .map(|(_, lamports)| *lamports)
+            .sum::<Option<u64>>();
+        let rows = balances
+            .iter()
+            .take(WALLET_ROWS)
+            .map(|(label, lamports)| (label.to_string(), *lamports))
+            .chain([("Total".to_string(), total)]);
+
+        for (i, (label, lamports)) in rows.enumerate() {
+            let y = 10 + 10 * i as u8;
+            let formatted = lamports.map_or(MISSING.to_string(), format_sol);
+            self.create_text(&label, 5, y, FONT_6X10);
+            let x = (128 - 5usize).saturating_sub(formatted.len() * 6);
+            self.create_text(&formatted, x as u8, y, FONT_6X10);
+        }
+        self.draw_time(time);
+    }
+
+    /// One page of the token list, `page` counts from zero.
+    pub fn draw_tokens(
+        &mut self,
+        tokens: Option<&[TokenBalance]>,
+        page: usize,
+        time: (&str, &str),
+    ) {
+        self.create_black_rectangle();
+        let pages = token_pages(tokens.map_or(0, <[_]>::len));
+        let title = format!("Tokens {}/{}", page + 1, pages);
+        self.create_text(&title, 5, 3, FONT_4X6);
+
+        let tokens = match tokens {
+            Some([]) => {
+                self.create_text("No tokens", centered_x("No tokens", 6), 24, FONT_6X10);
+                self.draw_time(time);
+                return;
+            }
+            Some(tokens) => tokens,
+            None => {
+                self.create_text(MISSING, centered_x(MISSING, 6), 24, FONT_6X10);
+                self.draw_time(time);
+                return;
+            }
+        };
+
+        let rows = tokens.iter().skip(page * TOKEN_ROWS).take(TOKEN_ROWS);
+        for (i, token) in rows.enumerate() {
+            let y = 11 + 10 * i as u8;
+            let amount = format_amount(token.ui_amount());
+            self.create_text(&token.symbol, 5, y, FONT_6X10);
+            let x = (128 - 5usize).saturating_sub(amount.len() * 6);
+            self.create_text(&amount, x as u8, y, FONT_6X10);
+        }
+        self.draw_time(time);
+    }
+
+    /// Total value in `quote` with the largest holdings below it.
+    pub fn draw_portfolio(
+        &mut self,
+        portfolio: Option<&Portfolio>,
+        quote: &Currency,
+        time: (&str, &str),
+    ) {
+        self.create_black_rectangle();
+        let title = format!("Portfolio {}", quote.label());
+        self.create_text(&title, 5, 3, FONT_4X6);
+
+        let total = portfolio.map_or(MISSING.to_string(), |p| quote.format(p.total));
+        self.create_text(&total, centered_x(&total, 6), 11, FONT_6X10);
+
+        let holdings = portfolio.map_or(&[][..], |p| &p.holdings);
+        for (i, holding) in holdings.iter().take(PORTFOLIO_ROWS).enumerate() {
+            let y = 25 + 7 * i as u8;
+            let value = quote.format(holding.value);
+            self.create_text(&holding.symbol, 5, y, FONT_4X6);
+            let x = (128 - 5usize).saturating_sub(value.chars().count() * 4);
+            self.create_text(&value, x as u8, y, FONT_4X6);
+        }
+        self.draw_time(time);
+    }
+
+    pub fn draw_slot_tps(&mut self, performance: Option<Performance>, time: (&str, &str)) {
+        self.create_black_rectangle();
+
+        let height_constant = 6 + 5;
+        let font_width_4x = 4;
+        let font_width_6x = 6;
+
+        let (slot, tps) = match performance {
+            Some(Performance {
+                slot,
+                tps,
+                non_vote_tps,
+                ..
+            }) => (
+                slot.to_string(),
+                format!(
+                    "{} / {}",
+                    tps,
+                    non_vote_tps.map_or(MISSING.to_string(), |tps| tps.to_string())
+                ),
+            ),
+            None => (MISSING.to_string(), MISSING.to_string()),
+        };
+
+        let slot_label = "Slot:";
+        let slot_label_y_c = 8;
+        let slot_value_y_c = slot_label_y_c + height_constant;
+
+        let tps_label = "TPS total / non-vote:";
+        let tps_label_y_c = slot_value_y_c + height_constant + 2;
+        let tps_value_y_c = tps_label_y_c + height_constant;
+
+        //slot
+        self.create_text(
+            slot_label,
+            centered_x(slot_label, font_width_4x),
+            slot_label_y_c,
+            FONT_4X6,
+        );
+        self.create_text(
+            &slot,
+            centered_x(&slot, font_width_6x),
+            slot_value_y_c,
+            FONT_6X10,
+        );
+
+        // tps
+        self.create_text(
+            tps_label,
+            centered_x(tps_label, font_width_4x),
+            tps_label_y_c,
+            FONT_4X6,
+        );
+        self.create_text(
+            &tps,
+            centered_x(&tps, font_width_6x),
+            tps_value_y_c,
+            FONT_6X10,
+        );
+        self.draw_time(time);
+    }
+
+    /// Epoch number, a progress bar over its slots and the time until it ends
+    /// at the recent `slot_time`.
+    pub fn draw_epoch(
+        &mut self,
+        epoch: Option<&EpochInfo>,
+        slot_time: Option<Duration>,
+        time: (&str, &str),
+    ) {
+        self.create_black_rectangle();
+        let Some(epoch) = epoch else {
+            let title = format!("Epoch {}", MISSING);
+            self.create_text(&title, centered_x(&title, 6), 4, FONT_6X10);
+            self.draw_time(time);
+            return;
+        };
+
+        let title = format!("Epoch {}", epoch.epoch);
+        self.create_text(&title, centered_x(&title, 6), 4, FONT_6X10);
+        self.draw_progress_bar(epoch.progress(), Point::new(5, 18), Size::new(118, 9));
+
+        let slots = format!(
+            "{} / {} ({:.1}%)",
+            epoch.slot_index,
+            epoch.slots_in_epoch,
+            epoch.progress() * 100.0
+        );
+        self.create_text(&slots, centered_x(&slots, 4), 30, FONT_4X6);
+
+        let eta = slot_time.map_or(MISSING.to_string(), |slot_time| {
+            format_eta(epoch.time_remaining(slot_time))
+        });
+        let eta = format!("ends in {}", eta);
+        self.create_text(&eta, centered_x(&eta, 6), 39, FONT_6X10);
+        self.draw_time(time);
+    }
+
+    /// Whether the validator is voting, with its stake, commission, vote lag
+    /// and the `skip_rate` of the current epoch below.
+    pub fn draw_validator(
+        &mut self,
+        status: Option<&ValidatorStatus>,
+        skip_rate: Option<f64>,
+        time: (&str, &str),
+    ) {
+        self.create_black_rectangle();
+        self.create_text("Validator", 5, 3, FONT_4X6);
+
+        let state = match status {
+            Some(status) if status.delinquent => "DELINQUENT",
+            Some(_) => "voting",
+            None => MISSING,
+        };
+        self.create_text(state, centered_x(state, 6), 11, FONT_6X10);
+
+        let Some(status) = status else {
+            self.draw_time(time);
+            return;
+        };
+        let rows = [
+            (
+                "Stake",
+                format!(
+                    "{} SOL",
+                    format_amount(lamports_to_sol(status.activated_stake))
+                ),
+            ),
+            ("Commission", format!("{}%", status.commission)),
+            ("Last vote", format!("{} slots ago", status.vote_lag())),
+            (
+                "Skip rate",
+                skip_rate.map_or(MISSING.to_string(), |rate| format!("{:.1}%", rate)),
+            ),
+        ];
+        for (i, (label, value)) in rows.iter().enumerate() {
+            let y = 25 + 7 * i as u8;
+            self.create_text(label, 5, y, FONT_4X6);
+            let x = (128 - 5usize).saturating_sub(value.len() * 4);
+            self.create_text(value, x as u8, y, FONT_4X6);
+        }
+        self.draw_time(time);
+    }
+
+    /// Countdown to the next leader slots of the validator at the recent
+    /// `slot_time`, and how many of its leader slots are left this epoch.
+    pub fn draw_leader(
+        &mut self,
+        schedule: Option<&LeaderSchedule>,
+        slot_time: Option<Duration>,
+        time: (&str, &str),
+    ) {
+        self.create_black_rectangle();
+        self.create_text("Leader slots", 5, 3, FONT_4X6);
+
+        let Some(schedule) = schedule else {
+            self.create_text(MISSING, centered_x(MISSING, 6), 13, FONT_6X10);
+            self.draw_time(time);
+            return;
+        };
+        let countdown = match schedule.next_slot() {
+            None => "none left".to_string(),
+            Some(next) if next == schedule.slot => "leader now".to_string(),
+            Some(_) => {
+                let until = slot_time.and_then(|slot_time| schedule.time_until_next(slot_time));
+                format!("in {}", until.map_or(MISSING.to_string(), format_countdown))
+            }
+        };
+        self.create_text(&countdown, centered_x(&countdown, 6), 13, FONT_6X10);
+
+        let next = schedule.next_slot();
+        let rows = [
+            (
+                "Next slot".to_string(),
+                next.map_or(MISSING.to_string(), |slot| {
+                    format!("{} x{}", slot, schedule.next_run())
+                }),
+            ),
+            (
+                "Slots to go".to_string(),
+                next.map_or(MISSING.to_string(), |slot| {
+                    (slot - schedule.slot).to_string()
+                }),
+            ),
+            (
+                format!("Left in epoch {}", schedule.epoch),
+                format!("{} / {}", schedule.upcoming().len(), schedule.slots.len()),
+            ),
+        ];
+        for (i, (label, value)) in rows.iter().enumerate() {
+            let y = 27 + 7 * i as u8;
+            self.create_text(label, 5, y, FONT_4X6);
+            let x = (128 - 5usize).saturating_sub(value.len() * 4);
+            self.create_text(value, x as u8, y, FONT_4X6);
+        }
+        self.draw_time(time);
+    }
+
+    /// Skip rate and blocks produced in the current epoch, with a row for
+    /// each of the last closed epochs.
+    pub fn draw_block_production(
+        &mut self,
+        history: Option<&ProductionHistory>,
+        time: (&str, &str),
+    ) {
+        self.create_black_rectangle();
+        let Some(current) = history.and_then(ProductionHistory::current) else {
+            self.create_text("Blocks", 5, 3, FONT_4X6);
+            self.create_text(MISSING, centered_x(MISSING, 6), 11, FONT_6X10);
+            self.draw_time(time);
+            return;
+        };
+        let skip_rate =
+            |rate: Option<f64>| rate.map_or(MISSING.to_string(), |r| format!("{:.1}%", r));
+
+        let title = format!("Blocks epoch {}", current.epoch);
+        self.create_text(&title, 5, 3, FONT_4X6);
+        let skipped = format!("{} skipped", skip_rate(current.skip_rate()));
+        self.create_text(&skipped, centered_x(&skipped, 6), 11, FONT_6X10);
+        let produced = format!(
+            "{} of {} leader slots",
+            current.blocks_produced, current.leader_slots
+        );
+        self.create_text(&produced, centered_x(&produced, 4), 23, FONT_4X6);
+
+        let past = history.into_iter().flat_map(ProductionHistory::past);
+        for (i, epoch) in past.take(PRODUCTION_ROWS).enumerate() {
+            let y = 32 + 7 * i as u8;
+            self.create_text(&epoch.epoch.to_string(), 5, y, FONT_4X6);
+            let blocks = format!("{}/{}", epoch.blocks_produced, epoch.leader_slots);
+            self.create_text(&blocks, 33, y, FONT_4X6);
+            let rate = skip_rate(epoch.skip_rate());
+            let x = (128 - 5usize).saturating_sub(rate.len() * 4);
+            self.create_text(&rate, x as u8, y, FONT_4X6);
+        }
+        self.draw_time(time);
+    }
+
+    /// SOL in the stake accounts of the wallets, split by activation state.
+    pub fn draw_stake(&mut self, stake: Option<&StakeAccounts>, time: (&str, &str)) {
+        self.create_black_rectangle();
+        let Some(stake) = stake else {
+            self.create_text("Stake", 5, 3, FONT_4X6);
+            self.create_text(MISSING, centered_x(MISSING, 6), 11, FONT_6X10);
+            self.draw_time(time);
+            return;
+        };
+        let title = match stake.accounts.len() {
+            1 => "Stake 1 account".to_string(),
+            n => format!("Stake {} accounts", n),
+        };
+        self.create_text(&title, 5, 3, FONT_4X6);
+        let totals = stake.totals();
+        let total = format!("{} SOL", format_sol(totals.total()));
+        self.create_text(&total, 5, 11, FONT_6X10);
+
+        let rows = [
+            ("Active", totals.active),
+            ("Activating", totals.activating),
+            ("Deactivating", totals.deactivating),
+            ("Inactive", totals.inactive),
+        ];
+        for (i, (label, lamports)) in rows.iter().enumerate() {
+            let y = 25 + 7 * i as u8;
+            self.create_text(label, 5, y, FONT_4X6);
+            let value = format_sol(*lamports);
+            let x = (128 - 5usize).saturating_sub(value.len() * 4);
+            self.create_text(&value, x as u8, y, FONT_4X6);
+        }
+        self.draw_time(time);
+    }
+
+    /// Estimated APY and the reward of the last epoch, over a bar chart of
+    /// the rewards of every kept epoch, oldest on the left.
+    pub fn draw_rewards(
+        &mut self,
+        history: Option<&RewardHistory>,
+        epoch_length: Duration,
+        time: (&str, &str),
+    ) {
+        self.create_black_rectangle();
+        self.create_text("Rewards", 5, 3, FONT_4X6);
+        let Some((history, latest)) = history.and_then(|h| h.latest().map(|latest| (h, latest)))
+        else {
+            self.create_text(MISSING, centered_x(MISSING, 6), 11, FONT_6X10);
+            self.draw_time(time);
+            return;
+        };
+        let apy = history
+            .apy(epoch_length)
+            .map_or(MISSING.to_string(), |apy| format!("{:.2}% APY", apy));
+        self.create_text(&apy, 5, 11, FONT_6X10);
+
+        self.create_text(&format!("Epoch {}", latest.epoch), 5, 24, FONT_4X6);
+        let amount = format!("+{:.4} SOL", lamports_to_sol(latest.amount));
+        let x = (128 - 5usize).saturating_sub(amount.len() * 4);
+        self.create_text(&amount, x as u8, 24, FONT_4X6);
+
+        // bars from zero up to the largest reward, one slot per kept epoch
+        let max = history.epochs.iter().map(|e| e.amount).max().unwrap_or(0);
+        let (bottom, height, width) = (52, 21, 10);
+        let slots = REWARDS_EPOCHS - history.epochs.len().min(REWARDS_EPOCHS);
+        for (i, epoch) in history.epochs.iter().enumerate() {
+            let filled = match max {
+                0 => 0,
+                max => (epoch.amount as f64 * height as f64 / max as f64).round() as u32,
+            };
+            let x = 5 + (slots + i) as i32 * (width + 2) as i32;
+            Rectangle::new(
+                Point::new(x, bottom - filled.max(1) as i32 + 1),
+                Size::new(width, filled.max(1)),
+            )
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+            .draw(&mut self.display)
+            .unwrap();
+        }
+        self.draw_time(time);
+    }
+
+    /// An outlined bar filled to `progress`, from 0 to 1.
+    fn draw_progress_bar(&mut self, progress: f64, top_left: Point, size: Size) {
+        Rectangle::new(top_left, size)
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+            .draw(&mut self.display)
+            .unwrap();
+        let inner = size.width.saturating_sub(4);
+        let filled = (inner as f64 * progress.clamp(0.0, 1.0)).round() as u32;
+        Rectangle::new(
+            top_left + Point::new(2, 2),
+            Size::new(filled, size.height.saturating_sub(4)),
+        )
+        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+        .draw(&mut self.display)
+        .unwrap();
+    }
+
+    /// SOL price in `quote` with its 24h change and a sparkline of the recent
+    /// `history`, plus the confidence and publish time of an on-chain feed.
+    pub fn draw_price(
+        &mut self,
+        price: Option<SolPrice>,
+        history: &[f64],
+        quote: &Currency,
+        time: (&str, &str),
+    ) {
+        self.create_black_rectangle();
+        let sol_price_label = format!("Sol {} Price:", quote.label());
+        let sol_price_formatted = price.map_or(MISSING.to_string(), |p| quote.format(p.price));
+
+        self.create_text(&sol_price_label, 5, 3, FONT_4X6);
+        self.create_text(&sol_price_formatted, 5, 11, FONT_6X10);
+
+        if let Some(change) = price.and_then(|p| p.change_24h) {
+            let formatted = format!("{:.2}%", change.abs());
+            let x = 128 - 5 - formatted.len() * 4;
+            self.create_text(&formatted, x as u8, 13, FONT_4X6);
+            self.draw_arrow(x as i32 - 7, 13, change >= 0.0);
+        }
+
+        let mut chart_top = 24;
+        if let Some(oracle) = price.and_then(|p| p.oracle) {
+            let details = format!(
+                "+/-{} at {}",
+                quote.format(oracle.confidence),
+                format_utc_time(oracle.publish_time)
+            );
+            self.create_text(&details, centered_x(&details, 4), 23, FONT_4X6);
+            chart_top = 31;
+        }
+        let chart = Rectangle::with_corners(Point::new(5, chart_top), Point::new(122, 51));
+        self.draw_sparkline(history, chart);
+        self.draw_time(time);
+    }
+
+    /// Latched alerts, drawn lit on dark or, when `inverted`, dark on lit so
+    /// alternating calls blink the whole panel.
+    pub fn draw_alert(&mut self, alerts: &[Triggered], inverted: bool) {
+        let (background, text) = if inverted {
+            (BinaryColor::On, BinaryColor::Off)
+        } else {
+            (BinaryColor::Off, BinaryColor::On)
+        };
+        Rectangle::new(Point::new(0, 0), Size::new(128, 64))
+            .into_styled(
+                PrimitiveStyleBuilder::new()
+                    .stroke_width(1)
+                    .stroke_color(BinaryColor::On)
+                    .fill_color(background)
+                    .build(),
+            )
+            .draw(&mut self.display)
+            .unwrap();
+
+        let title = match alerts.len() {
+            1 => "ALERT".to_string(),
+            count => format!("{} ALERTS", count),
+        };
+        self.create_colored_text(&title, centered_x(&title, 6), 3, FONT_6X10, text);
+        for (i, alert) in alerts.iter().take(ALERT_ROWS).enumerate() {
+            let y = 16 + 18 * i as u8;
+            self.create_colored_text(&alert.title, 5, y, FONT_6X10, text);
+            self.create_colored_text(&alert.value, 5, y + 10, FONT_4X6, text);
+        }
+        let hint = "press the button to clear";
+        self.create_colored_text(hint, centered_x(hint, 4), 55, FONT_4X6, text);
+    }
+
+    /// A 5x5 triangle pointing up or down with its top left corner at `x, y`.
+    fn draw_arrow(&mut self, x: i32, y: i32, up: bool) {
+        let (tip, base) = if up { (y, y + 4) } else { (y + 4, y) };
+        Triangle::new(
+            Point::new(x + 2, tip),
+            Point::new(x, base),
+            Point::new(x + 4, base),
+        )
+        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+        .draw(&mut self.display)
+        .unwrap();
+    }
+
+    /// `values` as a line spanning `area`, nothing below two values.
+    fn draw_sparkline(&mut self, values: &[f64], area: Rectangle) {
+        let points = sparkline_points(values, area);
+        if points.len() < 2 {
+            return;
+        }
+        Polyline::new(&points)
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+            .draw(&mut self.display)
+            .unwrap();
+    }
+}
+
+/// Scale `values` into `area`: oldest on the left, the lowest value on the
+/// bottom edge and the highest on the top edge. A flat history is drawn
+/// through the middle.
+pub fn sparkline_points(values: &[f64], area: Rectangle) -> Vec<Point> {
+    let (min, max) = values
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &v| {
+            (min.min(v), max.max(v))
+        });
+    let width = area.size.width.saturating_sub(1) as f64;
+    let height = area.size.height.saturating_sub(1) as f64;
+    let steps = values.len().saturating_sub(1).max(1) as f64;
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| {
+            let level = if max > min {
+                (value - min) / (max - min)
+            } else {
+                0.5
+            };
+            Point::new(
+                area.top_left.x + (i as f64 * width / steps).round() as i32,
+                area.top_left.y + ((1.0 - level) * height).round() as i32,
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_is_centered_on_the_panel() {
+        assert_eq!(centered_x("Wallet SOL:", 6), 31);
+        assert_eq!(centered_x("TPS:", 4), 56);
+        assert_eq!(centered_x("", 6), 64);
+        // wider than the panel sticks to the left edge instead of underflowing
+        assert_eq!(centered_x(&"x".repeat(30), 6), 0);
+    }
+
+    #[test]
+    fn sparkline_spans_its_area() {
+        let area = Rectangle::with_corners(Point::new(5, 24), Point::new(15, 34));
+        assert_eq!(
+            sparkline_points(&[10.0, 20.0, 15.0], area),
+            [Point::new(5, 34), Point::new(10, 24), Point::new(15, 29)]
+        );
+        assert_eq!(
+            sparkline_points(&[3.0, 3.0], area),
+            [Point::new(5, 29), Point::new(15, 29)]
+        );
+        assert!(sparkline_points(&[], area).is_empty());
+    }
+}
@@ -0,0 +1,1073 @@
+use std::{
+    error::Error,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    currency::Currency,
+    http::{parse_tps, Http, DEFAULT_TPS_SAMPLES},
+    leader::LeaderSchedule,
+    lst::{lst_holdings, Lst, LSTS},
+    price::PriceProviders,
+    production::{EpochProduction, ProductionHistory, PRODUCTION_KEY},
+    rewards::{EpochReward, RewardHistory, REWARDS_KEY},
+    rpc::{
+        AccountInfo, BatchHandle, BatchResponses, BlockProduction, EpochInfo, InflationReward,
+        LeaderSlots, PerformanceSample, ProgramAccount, RpcBatch, TokenAccount, VoteAccounts,
+        WithContext,
+    },
+    stake::{StakeAccounts, STAKER_OFFSET, STAKE_ACCOUNT_LEN, STAKE_PROGRAM_ID, WITHDRAWER_OFFSET},
+    state::{Clock, Metric, StateStore},
+    storage::{MemoryStorage, Storage},
+    tokens::{token_balances, Token, TOKEN_PROGRAM_IDS},
+    transport::Transport,
+    validator::validator_status,
+    wallets::Wallet,
+};
+
+/// Something the fetcher polls and publishes into the [`StateStore`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// Footer date and time.
+    Clock,
+    /// SOL balance of every watched wallet.
+    Balance,
+    /// Slot and TPS from the recent performance samples.
+    Performance,
+    /// SOL price.
+    Price,
+    /// SPL token balances of every watched wallet.
+    Tokens,
+    /// Prices of the SPL tokens.
+    TokenPrices,
+    /// SOL value of mSOL, jitoSOL and bSOL from their pool state accounts.
+    LstRates,
+    /// Current epoch and how far into it the cluster is.
+    Epoch,
+    /// Stake, commission and votes of the configured validator.
+    Validator,
+    /// Leader slots of the configured validator and the current slot.
+    LeaderSchedule,
+    /// Blocks the configured validator produced in its leader slots.
+    BlockProduction,
+    /// Stake accounts the watched wallets can stake or withdraw from.
+    Stake,
+    /// Rewards of those stake accounts in the last closed epoch.
+    Rewards,
+}
+
+impl Source {
+    pub fn default_interval(self) -> Duration {
+        match self {
+            Source::Clock => Duration::from_secs(10),
+            Source::Balance => Duration::from_secs(30),
+            Source::Performance => Duration::from_secs(10),
+            // CoinGecko's public API is rate limited
+            Source::Price => Duration::from_secs(60),
+            // two getTokenAccountsByOwner per wallet, and holdings rarely move
+            Source::Tokens => Duration::from_secs(60),
+            Source::TokenPrices => Duration::from_secs(60),
+            // the pools update their rate once per epoch
+            Source::LstRates => Duration::from_secs(300),
+            Source::Epoch => Duration::from_secs(30),
+            Source::Validator => Duration::from_secs(30),
+            // a getSlot, the schedule itself is only fetched once per epoch
+            Source::LeaderSchedule => Duration::from_secs(10),
+            Source::BlockProduction => Duration::from_secs(60),
+            // two getProgramAccounts per wallet, and stake only moves per epoch
+            Source::Stake => Duration::from_secs(120),
+            // only asks once the stake accounts are in a new epoch
+            Source::Rewards => Duration::from_secs(120),
+        }
+    }
+
+    /// Served by the Solana RPC endpoint, so it can share a batch request
+    /// with the other RPC sources due at the same time.
+    pub fn is_rpc(self) -> bool {
+        matches!(
+            self,
+            Source::Balance
+                | Source::Performance
+                | Source::Tokens
+                | Source::LstRates
+                | Source::Epoch
+                | Source::Validator
+                | Source::LeaderSchedule
+                | Source::BlockProduction
+                | Source::Stake
+                | Source::Rewards
+        )
+    }
+
+    /// Age after which a value of this source is shown as stale.
+    pub fn max_age(self) -> Duration {
+        self.default_interval() * 3
+    }
+}
+
+/// An RPC source added to the current batch, waiting for its response.
+enum Pending {
+    /// Balance of the wallet at this index.
+    Balance(usize, BatchHandle<WithContext<u64>>),
+    Performance(BatchHandle<Vec<PerformanceSample>>),
+    /// Token accounts of every wallet under both token programs.
+    Tokens(Vec<BatchHandle<WithContext<Vec<TokenAccount>>>>),
+    /// State account of each known LST pool.
+    LstRates(Vec<(Lst, BatchHandle<WithContext<Option<AccountInfo>>>)>),
+    Epoch(BatchHandle<EpochInfo>),
+    /// The vote account and the slot.
+    Validator {
+        accounts: BatchHandle<VoteAccounts>,
+        slot: BatchHandle<u64>,
+    },
+    /// The slot, plus the epoch and its leader schedule when the stored
+    /// schedule is missing or belongs to an earlier epoch.
+    LeaderSchedule {
+        identity: String,
+        slot: BatchHandle<u64>,
+        schedule: Option<(BatchHandle<EpochInfo>, BatchHandle<LeaderSlots>)>,
+    },
+    /// The epoch, to know which one the block production belongs to.
+    BlockProduction {
+        identity: String,
+        epoch: BatchHandle<EpochInfo>,
+        production: BatchHandle<WithContext<BlockProduction>>,
+    },
+    /// The epoch the stake is in, and the stake accounts of every wallet as
+    /// staker and as withdrawer.
+    Stake {
+        epoch: BatchHandle<EpochInfo>,
+        accounts: Vec<BatchHandle<Vec<ProgramAccount>>>,
+    },
+    /// Rewards of the stake accounts for this epoch.
+    Rewards {
+        epoch: u64,
+        rewards: BatchHandle<Vec<Option<InflationReward>>>,
+    },
+}
+
+struct Schedule {
+    source: Source,
+    interval: Duration,
+    next_due: Option<Instant>,
+}
+
+/// Polls each [`Source`] on its own interval and publishes the results, so
+/// rendering never waits on the network. Meant to run on its own thread.
+pub struct Fetcher<T> {
+    http: Http<T>,
+    store: StateStore,
+    wallets: Vec<String>,
+    tokens: Vec<Token>,
+    tps_samples: usize,
+    prices: PriceProviders<T>,
+    quote: Currency,
+    vote_account: Option<String>,
+    storage: Box<dyn Storage>,
+    schedule: Vec<Schedule>,
+}
+
+impl<T: Transport> Fetcher<T> {
+    /// Every source is fetched on the first [`Fetcher::poll`]. Balances are
+    /// published in the order of `wallets`.
+    pub fn new(http: Http<T>, store: StateStore, wallets: &[Wallet], sources: &[Source]) -> Self {
+        let mut schedule: Vec<Schedule> = Vec::new();
+        for &source in sources {
+            if schedule.iter().all(|s| s.source != source) {
+                schedule.push(Schedule {
+                    source,
+                    interval: source.default_interval(),
+                    next_due: None,
+                });
+            }
+        }
+        store.update(|s| s.balances.resize(wallets.len(), Metric::default()));
+        Self {
+            http,
+            store,
+            wallets: wallets.iter().map(|w| w.address.clone()).collect(),
+            tokens: Vec::new(),
+            tps_samples: DEFAULT_TPS_SAMPLES,
+            prices: PriceProviders::default(),
+            quote: Currency::default(),
+            vote_account: None,
+            storage: Box::new(MemoryStorage::new()),
+            schedule,
+        }
+    }
+
+    /// Mints shown on the token page, every held mint when empty.
+    pub fn with_tokens(mut self, tokens: &[Token]) -> Self {
+        self.tokens = tokens.to_vec();
+        self
+    }
+
+    /// Number of performance samples the TPS is averaged over.
+    pub fn with_tps_samples(mut self, samples: usize) -> Self {
+        self.tps_samples = samples.max(1);
+        self
+    }
+
+    /// Where the SOL and token prices come from, in order of preference.
+    pub fn with_price_providers(mut self, prices: PriceProviders<T>) -> Self {
+        self.prices = prices;
+        self
+    }
+
+    /// Currency the prices are quoted in, USD by default.
+    pub fn with_quote_currency(mut self, quote: Currency) -> Self {
+        self.quote = quote;
+        self.store.update(|s| s.quote = quote);
+        self
+    }
+
+    /// Vote account of the validator behind [`Source::Validator`].
+    pub fn with_vote_account(mut self, vote_account: Option<String>) -> Self {
+        self.vote_account = vote_account;
+        self
+    }
+
+    /// Where the block production and reward histories are kept across
+    /// restarts, in memory by default. Saved histories are published right
+    /// away.
+    pub fn with_storage(mut self, mut storage: impl Storage + 'static) -> Self {
+        let production = load(&mut storage, PRODUCTION_KEY, ProductionHistory::from_bytes);
+        let rewards = load(&mut storage, REWARDS_KEY, RewardHistory::from_bytes);
+        self.store.update(|s| {
+            s.block_production.value = production;
+            s.rewards.value = rewards;
+        });
+        self.storage = Box::new(storage);
+        self
+    }
+
+    pub fn with_interval(mut self, source: Source, interval: Duration) -> Self {
+        for schedule in self.schedule.iter_mut().filter(|s| s.source == source) {
+            schedule.interval = interval;
+        }
+        self
+    }
+
+    pub fn http(&self) -> &Http<T> {
+        &self.http
+    }
+
+    /// Fetch every source that is due at `now` and return when the next one
+    /// will be, or `None` when there is nothing to poll. The due RPC sources
+    /// go out together as a single batch request.
+    pub fn poll(&mut self, now: Instant) -> Option<Instant> {
+        let mut due = Vec::new();
+        for schedule in &mut self.schedule {
+            if schedule.next_due.is_some_and(|due| due > now) {
+                continue;
+            }
+            due.push(schedule.source);
+            schedule.next_due = Some(now + schedule.interval);
+        }
+
+        let (rpc, others): (Vec<Source>, Vec<Source>) =
+            due.into_iter().partition(|source| source.is_rpc());
+        if !rpc.is_empty() {
+            self.fetch_rpc(&rpc, now);
+        }
+        for source in others {
+            self.fetch(source, now);
+        }
+        self.schedule.iter().filter_map(|s| s.next_due).min()
+    }
+
+    pub fn run(mut self) {
+        while let Some(next_due) = self.poll(Instant::now()) {
+            std::thread::sleep(next_due.saturating_duration_since(Instant::now()));
+        }
+    }
+
+    fn fetch(&mut self, source: Source, now: Instant) {
+        let http = &mut self.http;
+        match source {
+            Source::Clock => {
+                let result = http
+                    .utc_offset_time()
+                    .map(|(time, date)| Clock { time, date });
+                log_error(source, &result);
+                self.store.update(|s| s.clock.record(result, now));
+            }
+            Source::Price => {
+                let result = self.prices.sol_price(http, &self.quote);
+                log_error(source, &result);
+                self.store.update(|s| {
+                    if let Ok(price) = &result {
+                        s.price_history.push(price.price);
+                    }
+                    s.price.record(result, now)
+                });
+            }
+            Source::TokenPrices => {
+                let mints = self.token_mints();
+                let result = self
+                    .prices
+                    .token_prices(&mut self.http, &mints, &self.quote);
+                log_error(source, &result);
+                self.store.update(|s| s.token_prices.record(result, now));
+            }
+            Source::Balance
+            | Source::Performance
+            | Source::Tokens
+            | Source::LstRates
+            | Source::Epoch
+            | Source::Validator
+            | Source::LeaderSchedule
+            | Source::BlockProduction
+            | Source::Stake
+            | Source::Rewards => self.fetch_rpc(&[source], now),
+        }
+    }
+
+    /// Mints to price: the allowlist, or every mint held when there is none.
+    fn token_mints(&self) -> Vec<String> {
+        if !self.tokens.is_empty() {
+            return self.tokens.iter().map(|token| token.mint.clone()).collect();
+        }
+        let held = self.store.snapshot().tokens.value.unwrap_or_default();
+        held.into_iter().map(|token| token.mint).collect()
+    }
+
+    /// Save a history when an epoch closed, only then so the flash is not
+    /// written on every fetch.
+    fn save(&mut self, key: &str, value: &[u8]) {
+        if let Err(e) = self.storage.save(key, value) {
+            println!("Error saving {}: {}", key, e);
+        }
+    }
+
+    /// Identity of the validator from the last status, for its leader schedule
+    /// and block production.
+    fn validator_identity(&self) -> Option<String> {
+        let status = self.store.snapshot().validator.value;
+        status.map(|status| status.node_pubkey)
+    }
+
+    /// Fetch the RPC `sources` in one batch request. When the whole request
+    /// fails every source that made a call records the same error.
+    fn fetch_rpc(&mut self, sources: &[Source], now: Instant) {
+        let mut batch = RpcBatch::new();
+        let mut pending = Vec::new();
+        // sources with a call in the batch, a source with nothing to ask has
+        // no error to show when the batch fails
+        let mut batched = Vec::new();
+        for &source in sources {
+            let calls = batch.len();
+            let handles = match source {
+                Source::Balance => self
+                    .wallets
+                    .iter()
+                    .enumerate()
+                    .map(|(i, wallet)| batch.get_balance(wallet).map(|h| Pending::Balance(i, h)))
+                    .collect(),
+                Source::Performance => vec![batch
+                    .get_recent_performance_samples(self.tps_samples)
+                    .map(Pending::Performance)],
+                Source::Tokens => {
+                    let handles = self
+                        .wallets
+                        .iter()
+                        .flat_map(|wallet| TOKEN_PROGRAM_IDS.map(|program| (wallet, program)))
+                        .map(|(wallet, program)| batch.get_token_accounts_by_owner(wallet, program))
+                        .collect::<Result<_, _>>();
+                    vec![handles.map(Pending::Tokens)]
+                }
+                Source::LstRates => {
+                    let handles = LSTS
+                        .iter()
+                        .map(|&lst| batch.get_account_info(lst.state).map(|h| (lst, h)))
+                        .collect::<Result<_, _>>();
+                    vec![handles.map(Pending::LstRates)]
+                }
+                Source::Epoch => vec![batch.get_epoch_info().map(Pending::Epoch)],
+                Source::Validator => {
+                    let Some(vote_account) = &self.vote_account else {
+                        continue;
+                    };
+                    vec![add_validator(&mut batch, vote_account)]
+                }
+                Source::LeaderSchedule => {
+                    // the identity comes with the first validator status
+                    let Some(identity) = self.validator_identity() else {
+                        continue;
+                    };
+                    let schedule = self.store.snapshot().leader.value;
+                    let current = schedule.is_some_and(|schedule| schedule.is_current());
+                    vec![add_leader_schedule(&mut batch, identity, !current)]
+                }
+                Source::BlockProduction => {
+                    let Some(identity) = self.validator_identity() else {
+                        continue;
+                    };
+                    vec![add_block_production(&mut batch, identity)]
+                }
+                Source::Stake => vec![add_stake(&mut batch, &self.wallets)],
+                Source::Rewards => {
+                    // the stake accounts tell which epoch closed last
+                    let snapshot = self.store.snapshot();
+                    let Some(stake) = snapshot.stake.value else {
+                        continue;
+                    };
+                    let Some(epoch) = stake.epoch.checked_sub(1) else {
+                        continue;
+                    };
+                    let known = snapshot.rewards.value;
+                    if stake.accounts.is_empty() || known.is_some_and(|h| h.covers(epoch)) {
+                        continue;
+                    }
+                    let addresses: Vec<String> =
+                        stake.accounts.into_iter().map(|a| a.pubkey).collect();
+                    vec![batch
+                        .get_inflation_reward(&addresses, epoch)
+                        .map(|rewards| Pending::Rewards { epoch, rewards })]
+                }
+                Source::Clock | Source::Price | Source::TokenPrices => continue,
+            };
+            for handle in handles {
+                match handle {
+                    Ok(handle) => pending.push(handle),
+                    Err(e) => println!("Error adding {:?} to the batch: {}", source, e),
+                }
+            }
+            if batch.len() > calls {
+                batched.push(source);
+            }
+        }
+
+        if batch.is_empty() {
+            return;
+        }
+        match self.http.send_batch(batch) {
+            Ok(responses) => {
+                for pending in &pending {
+                    self.record(pending, &responses, now);
+                }
+            }
+            Err(e) => {
+                for source in batched {
+                    println!("Error fetching {:?}: {}", source, e);
+                    self.store.update(|s| match source {
+                        Source::Balance => {
+                            for balance in &mut s.balances {
+                                balance.record_error(&e);
+                            }
+                        }
+                        Source::Performance => s.performance.record_error(&e),
+                        Source::Tokens => {
+                            s.tokens.record_error(&e);
+                            s.lst_holdings.record_error(&e);
+                        }
+                        Source::LstRates => s.lst_rates.record_error(&e),
+                        Source::Epoch => s.epoch.record_error(&e),
+                        Source::Validator => s.validator.record_error(&e),
+                        Source::LeaderSchedule => s.leader.record_error(&e),
+                        Source::BlockProduction => s.block_production.record_error(&e),
+                        Source::Stake => s.stake.record_error(&e),
+                        Source::Rewards => s.rewards.record_error(&e),
+                        Source::Clock | Source::Price | Source::TokenPrices => {}
+                    });
+                }
+            }
+        }
+    }
+
+    fn record(&mut self, pending: &Pending, responses: &BatchResponses, now: Instant) {
+        match pending {
+            Pending::Balance(i, handle) => {
+                let result = responses.get(handle).map(|balance| balance.value);
+                log_error(Source::Balance, &result);
+                self.store.update(|s| s.balances[*i].record(result, now));
+            }
+            Pending::Performance(handle) => {
+                let result = responses
+                    .get(handle)
+                    .and_then(|samples| parse_tps(&samples));
+                log_error(Source::Performance, &result);
+                self.store.update(|s| s.performance.record(result, now));
+            }
+            Pending::Tokens(handles) => {
+                let accounts = handles
+                    .iter()
+                    .map(|handle| responses.get(handle).map(|accounts| accounts.value))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(|accounts| accounts.concat());
+                let (result, lsts) = match accounts {
+                    Ok(accounts) => (
+                        token_balances(&self.tokens, &accounts),
+                        lst_holdings(&self.tokens, &accounts),
+                    ),
+                    Err(e) => (Err(e.to_string().into()), Err(e)),
+                };
+                log_error(Source::Tokens, &result);
+                self.store.update(|s| {
+                    s.tokens.record(result, now);
+                    s.lst_holdings.record(lsts, now);
+                });
+            }
+            Pending::LstRates(handles) => {
+                let result = handles
+                    .iter()
+                    .map(|(lst, handle)| {
+                        let account = responses.get(handle)?.value;
+                        lst.rate(&account.ok_or_else(|| format!("{} not found", lst.state))?)
+                    })
+                    .collect::<Result<Vec<_>, _>>();
+                log_error(Source::LstRates, &result);
+                self.store.update(|s| s.lst_rates.record(result, now));
+            }
+            Pending::Epoch(handle) => {
+                let result = responses.get(handle);
+                log_error(Source::Epoch, &result);
+                self.store.update(|s| s.epoch.record(result, now));
+            }
+            Pending::Validator { accounts, slot } => {
+                let vote_account = self.vote_account.as_deref().unwrap_or_default();
+                let result = (|| {
+                    validator_status(vote_account, responses.get(accounts)?, responses.get(slot)?)
+                })();
+                log_error(Source::Validator, &result);
+                self.store.update(|s| s.validator.record(result, now));
+            }
+            Pending::LeaderSchedule {
+                identity,
+                slot,
+                schedule,
+            } => {
+                let previous = self.store.snapshot().leader.value;
+                let result = (|| {
+                    let mut leader = match schedule {
+                        Some((epoch, schedule)) => LeaderSchedule::new(
+                            identity,
+                            &responses.get(epoch)?,
+                            responses.get(schedule)?,
+                        ),
+                        None => previous.ok_or("the leader schedule was not fetched yet")?,
+                    };
+                    leader.slot = responses.get(slot)?;
+                    Ok(leader)
+                })();
+                log_error(Source::LeaderSchedule, &result);
+                self.store.update(|s| s.leader.record(result, now));
+            }
+            Pending::BlockProduction {
+                identity,
+                epoch,
+                production,
+            } => {
+                let result = (|| {
+                    let epoch = responses.get(epoch)?.epoch;
+                    let production = responses.get(production)?.value;
+                    Ok::<_, Box<dyn Error>>(EpochProduction::new(epoch, &production, identity))
+                })();
+                log_error(Source::BlockProduction, &result);
+                let result = result.map(|production| {
+                    let mut history = self.store.snapshot().block_production.value;
+                    let history = history.get_or_insert_with(ProductionHistory::default);
+                    if history.record(production) {
+                        self.save(PRODUCTION_KEY, &history.to_bytes());
+                    }
+                    history.clone()
+                });
+                self.store
+                    .update(|s| s.block_production.record(result, now));
+            }
+            Pending::Stake { epoch, accounts } => {
+                let result = (|| {
+                    let epoch = responses.get(epoch)?.epoch;
+                    let accounts = accounts
+                        .iter()
+                        .map(|handle| responses.get(handle))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    StakeAccounts::new(epoch, accounts)
+                })();
+                log_error(Source::Stake, &result);
+                self.store.update(|s| s.stake.record(result, now));
+            }
+            Pending::Rewards { epoch, rewards } => {
+                let result = responses.get(rewards);
+                log_error(Source::Rewards, &result);
+                let result = result.map(|rewards| {
+                    let mut history = self.store.snapshot().rewards.value;
+                    let history = history.get_or_insert_with(RewardHistory::default);
+                    if history.record(EpochReward::new(*epoch, &rewards)) {
+                        self.save(REWARDS_KEY, &history.to_bytes());
+                    }
+                    history.clone()
+                });
+                self.store.update(|s| s.rewards.record(result, now));
+            }
+        }
+    }
+}
+
+/// The epoch, and the stake accounts with a wallet as their staker or their
+/// withdrawer. The same account usually matches both and is counted once.
+fn add_stake(batch: &mut RpcBatch, wallets: &[String]) -> Result<Pending, Box<dyn Error>> {
+    let epoch = batch.get_epoch_info()?;
+    let accounts = wallets
+        .iter()
+        .flat_map(|wallet| [STAKER_OFFSET, WITHDRAWER_OFFSET].map(|offset| (wallet, offset)))
+        .map(|(wallet, offset)| {
+            batch.get_program_accounts(STAKE_PROGRAM_ID, STAKE_ACCOUNT_LEN, offset, wallet)
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Pending::Stake { epoch, accounts })
+}
+
+fn add_block_production(batch: &mut RpcBatch, identity: String) -> Result<Pending, Box<dyn Error>> {
+    Ok(Pending::BlockProduction {
+        epoch: batch.get_epoch_info()?,
+        production: batch.get_block_production(&identity)?,
+        identity,
+    })
+}
+
+/// The slot, and with `schedule` the epoch and the leader schedule as well.
+fn add_leader_schedule(
+    batch: &mut RpcBatch,
+    identity: String,
+    schedule: bool,
+) -> Result<Pending, Box<dyn Error>> {
+    let slot = batch.get_slot()?;
+    let schedule = if schedule {
+        Some((
+            batch.get_epoch_info()?,
+            batch.get_leader_schedule(&identity)?,
+        ))
+    } else {
+        None
+    };
+    Ok(Pending::LeaderSchedule {
+        identity,
+        slot,
+        schedule,
+    })
+}
+
+fn add_validator(batch: &mut RpcBatch, vote_account: &str) -> Result<Pending, Box<dyn Error>> {
+    Ok(Pending::Validator {
+        accounts: batch.get_vote_accounts(vote_account)?,
+        slot: batch.get_slot()?,
+    })
+}
+
+/// The value saved under `key`, `None` when there is none or it is unreadable.
+fn load<V>(
+    storage: &mut impl Storage,
+    key: &str,
+    from_bytes: impl FnOnce(&[u8]) -> Result<V, Box<dyn Error>>,
+) -> Option<V> {
+    let saved = storage
+        .load(key)
+        .and_then(|bytes| bytes.map(|bytes| from_bytes(&bytes)).transpose());
+    saved.unwrap_or_else(|e| {
+        println!("Error loading {}: {}", key, e);
+        None
+    })
+}
+
+fn log_error<T>(source: Source, result: &Result<T, Box<dyn Error>>) {
+    if let Err(e) = result {
+        println!("Error fetching {:?}: {}", source, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock::MockTransport,
+        simulator::{SAMPLE_VOTE_ACCOUNT, SAMPLE_VOTE_IDENTITY},
+        state::Performance,
+    };
+
+    const BALANCE_BATCH: &str =
+        r#"[{"jsonrpc":"2.0","result":{"context":{"slot":1},"value":12345678901},"id":1}]"#;
+
+    fn wallets(count: usize) -> Vec<Wallet> {
+        (0..count)
+            .map(|i| Wallet::new(&format!("W{}", i), &format!("wallet{}", i)))
+            .collect()
+    }
+
+    /// The response in `fixture`, answering the batch call `id`.
+    fn fixture_with_id(fixture: &str, id: u64) -> serde_json::Value {
+        let mut response: serde_json::Value = serde_json::from_str(fixture).unwrap();
+        response["id"] = id.into();
+        response
+    }
+
+    /// An RPC endpoint answering one batch request with each of `batches`.
+    fn batch_responses(batches: &[&[serde_json::Value]]) -> Http<MockTransport> {
+        let transport = batches
+            .iter()
+            .fold(MockTransport::new(), |transport, batch| {
+                transport.respond(200, serde_json::to_string(batch).unwrap())
+            });
+        Http::init(transport, "https://rpc.example.com")
+    }
+
+    /// Body of the batch request at `index`.
+    fn sent_batch(fetcher: &Fetcher<MockTransport>, index: usize) -> serde_json::Value {
+        let request = &fetcher.http().transport().requests()[index];
+        serde_json::from_slice(request.body.as_deref().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn sources_are_polled_on_their_own_interval() {
+        let transport = MockTransport::new()
+            .respond(200, BALANCE_BATCH)
+            .respond(200, include_str!("../fixtures/coingecko_simple_price.json"))
+            .respond(200, include_str!("../fixtures/coingecko_simple_price.json"));
+        let http = Http::init(transport, "https://rpc.example.com");
+        let store = StateStore::new();
+        let mut fetcher = Fetcher::new(
+            http,
+            store.clone(),
+            &wallets(1),
+            &[Source::Balance, Source::Price, Source::Price],
+        )
+        .with_interval(Source::Price, Duration::from_secs(5));
+
+        let start = Instant::now();
+        assert_eq!(fetcher.poll(start), Some(start + Duration::from_secs(5)));
+        let snapshot = store.snapshot();
+        assert_eq!(snapshot.balances[0].value, Some(12_345_678_901));
+        assert_eq!(snapshot.balances[0].fetched_at, Some(start));
+        assert_eq!(snapshot.price.value.map(|p| p.price), Some(187.42));
+
+        // nothing is due yet
+        assert_eq!(
+            fetcher.poll(start + Duration::from_secs(1)),
+            Some(start + Duration::from_secs(5))
+        );
+        assert_eq!(fetcher.http().transport().requests().len(), 2);
+
+        // only the price is due again
+        let later = start + Duration::from_secs(5);
+        assert_eq!(fetcher.poll(later), Some(later + Duration::from_secs(5)));
+        assert_eq!(fetcher.http().transport().requests().len(), 3);
+        assert_eq!(fetcher.http().transport().remaining(), 0);
+        let history: Vec<f64> = store.snapshot().price_history.iter().collect();
+        assert_eq!(history, [187.42, 187.42]);
+    }
+
+    #[test]
+    fn failures_are_stored_next_to_the_last_good_value() {
+        let transport = MockTransport::new()
+            .respond(200, BALANCE_BATCH)
+            .respond(429, include_str!("../fixtures/rpc_rate_limited.json"));
+        let http = Http::init(transport, "https://rpc.example.com");
+        let store = StateStore::new();
+        let mut fetcher = Fetcher::new(http, store.clone(), &wallets(1), &[Source::Balance]);
+
+        let start = Instant::now();
+        let next = fetcher.poll(start).unwrap();
+        fetcher.poll(next);
+
+        let balance = store.snapshot().balances[0].clone();
+        assert_eq!(balance.value, Some(12_345_678_901));
+        assert_eq!(balance.fetched_at, Some(start));
+        assert!(balance
+            .last_error
+            .unwrap()
+            .starts_with("RPC error 429: Too many requests"));
+    }
+
+    #[test]
+    fn a_failed_batch_only_fails_the_sources_in_it() {
+        let transport =
+            MockTransport::new().respond(429, include_str!("../fixtures/rpc_rate_limited.json"));
+        let http = Http::init(transport, "https://rpc.example.com");
+        let store = StateStore::new();
+        // without a vote account the validator sources ask nothing
+        let mut fetcher = Fetcher::new(
+            http,
+            store.clone(),
+            &wallets(1),
+            &[Source::Balance, Source::Validator, Source::BlockProduction],
+        );
+
+        fetcher.poll(Instant::now());
+        let snapshot = store.snapshot();
+        assert!(snapshot.balances[0].last_error.is_some());
+        assert_eq!(snapshot.validator.last_error, None);
+        assert_eq!(snapshot.block_production.last_error, None);
+    }
+
+    #[test]
+    fn due_rpc_sources_share_one_request() {
+        let transport = MockTransport::new()
+            .respond(200, include_str!("../fixtures/batch_refresh.json"))
+            .respond(200, include_str!("../fixtures/coingecko_simple_price.json"));
+        let http = Http::init(transport, "https://rpc.example.com");
+        let store = StateStore::new();
+        let mut fetcher = Fetcher::new(
+            http,
+            store.clone(),
+            &wallets(1),
+            &[Source::Balance, Source::Price, Source::Performance],
+        )
+        .with_tps_samples(3);
+
+        fetcher.poll(Instant::now());
+        let requests = fetcher.http().transport().requests();
+        assert_eq!(requests.len(), 2);
+        let batch = sent_batch(&fetcher, 0);
+        assert_eq!(batch[0]["method"], "getBalance");
+        assert_eq!(batch[1]["method"], "getRecentPerformanceSamples");
+        assert_eq!(batch[1]["params"], serde_json::json!([3]));
+
+        let snapshot = store.snapshot();
+        assert_eq!(snapshot.balances[0].value, Some(12_345_678_901));
+        assert_eq!(
+            snapshot.performance.value,
+            Some(Performance {
+                slot: 315_482_100,
+                tps: 4_090,
+                non_vote_tps: Some(1_039),
+                slot_time: Some(Duration::from_millis(400)),
+            })
+        );
+        assert_eq!(snapshot.price.value.map(|p| p.price), Some(187.42));
+    }
+
+    #[test]
+    fn every_wallet_balance_is_fetched_in_config_order() {
+        let transport = MockTransport::new().respond(
+            200,
+            r#"[{"jsonrpc":"2.0","result":{"context":{"slot":1},"value":2},"id":2},
+                {"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid param"},"id":3},
+                {"jsonrpc":"2.0","result":{"context":{"slot":1},"value":1},"id":1}]"#,
+        );
+        let http = Http::init(transport, "https://rpc.example.com");
+        let store = StateStore::new();
+        let mut fetcher = Fetcher::new(http, store.clone(), &wallets(3), &[Source::Balance]);
+        assert_eq!(store.snapshot().balances.len(), 3);
+
+        fetcher.poll(Instant::now());
+        let balances = store.snapshot().balances;
+        assert_eq!(balances[0].value, Some(1));
+        assert_eq!(balances[1].value, Some(2));
+        assert_eq!(balances[2].value, None);
+        assert_eq!(
+            balances[2].last_error.as_deref(),
+            Some("RPC error -32602: Invalid param")
+        );
+
+        let batch = sent_batch(&fetcher, 0);
+        assert_eq!(batch[2]["params"], serde_json::json!(["wallet2"]));
+    }
+
+    #[test]
+    fn token_accounts_of_both_programs_are_summed() {
+        let accounts = include_str!("../fixtures/get_token_accounts_by_owner.json");
+        let token_program = fixture_with_id(accounts, 1);
+        let mut token_2022 = fixture_with_id(accounts, 2);
+        token_2022["result"]["value"] = serde_json::json!([]);
+        let http = batch_responses(&[&[token_2022, token_program]]);
+        let store = StateStore::new();
+        let usdc = Token::new("USDC", "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+        let mut fetcher =
+            Fetcher::new(http, store.clone(), &wallets(1), &[Source::Tokens]).with_tokens(&[usdc]);
+
+        fetcher.poll(Instant::now());
+        let tokens = store.snapshot().tokens.value.unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].ui_amount(), 1_250.75);
+
+        let batch = sent_batch(&fetcher, 0);
+        assert_eq!(batch[0]["params"][1]["programId"], TOKEN_PROGRAM_IDS[0]);
+        assert_eq!(batch[1]["params"][1]["programId"], TOKEN_PROGRAM_IDS[1]);
+    }
+
+    #[test]
+    fn held_mints_are_priced_without_an_allowlist() {
+        let transport = MockTransport::new()
+            .respond(200, include_str!("../fixtures/coingecko_token_price.json"));
+        let http = Http::init(transport, "https://rpc.example.com");
+        let store = StateStore::new();
+        store.update(|s| s.tokens.value = Some(crate::simulator::sample_tokens()));
+        let mut fetcher = Fetcher::new(http, store.clone(), &wallets(1), &[Source::TokenPrices]);
+
+        fetcher.poll(Instant::now());
+        let uri = &fetcher.http().transport().requests()[0].uri;
+        assert!(uri.contains("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v,2b1k"));
+        let prices = store.snapshot().token_prices.value.unwrap();
+        assert_eq!(prices.len(), 2);
+    }
+
+    #[test]
+    fn validator_status_leaves_block_production_to_its_source() {
+        let accounts = fixture_with_id(include_str!("../fixtures/get_vote_accounts.json"), 1);
+        let slot = serde_json::json!({"jsonrpc": "2.0", "result": 315_482_100u64, "id": 2});
+        let http = batch_responses(&[&[slot, accounts]]);
+        let store = StateStore::new();
+        store.update(|s| s.validator.value = Some(crate::simulator::sample_validator()));
+        let mut fetcher = Fetcher::new(http, store.clone(), &wallets(1), &[Source::Validator])
+            .with_vote_account(Some(SAMPLE_VOTE_ACCOUNT.to_string()));
+
+        // the identity is known, but the skip rate is the block production page's
+        fetcher.poll(Instant::now());
+        let batch = sent_batch(&fetcher, 0);
+        assert_eq!(batch.as_array().unwrap().len(), 2);
+        assert_eq!(batch[0]["params"][0]["votePubkey"], SAMPLE_VOTE_ACCOUNT);
+        assert_eq!(batch[1]["method"], "getSlot");
+
+        let status = store.snapshot().validator.value.unwrap();
+        assert_eq!(status.node_pubkey, SAMPLE_VOTE_IDENTITY);
+        assert_eq!(status.vote_lag(), 3);
+    }
+
+    #[test]
+    fn leader_schedule_is_fetched_once_per_epoch() {
+        let slot =
+            |slot: u64, id: u64| serde_json::json!({"jsonrpc": "2.0", "result": slot, "id": id});
+        let first = [
+            slot(315_482_100, 1),
+            fixture_with_id(include_str!("../fixtures/get_epoch_info.json"), 2),
+            fixture_with_id(include_str!("../fixtures/get_leader_schedule.json"), 3),
+        ];
+        let http = batch_responses(&[&first, &[slot(315_482_400, 4)]]);
+        let store = StateStore::new();
+        store.update(|s| s.validator.value = Some(crate::simulator::sample_validator()));
+        let mut fetcher = Fetcher::new(http, store.clone(), &wallets(1), &[Source::LeaderSchedule]);
+
+        let next = fetcher.poll(Instant::now()).unwrap();
+        assert_eq!(
+            store.snapshot().leader.value,
+            Some(crate::simulator::sample_leader_schedule())
+        );
+
+        // only the slot moves on within the epoch
+        fetcher.poll(next);
+        let batch = sent_batch(&fetcher, 1);
+        assert_eq!(batch.as_array().unwrap().len(), 1);
+        assert_eq!(batch[0]["method"], "getSlot");
+        let leader = store.snapshot().leader.value.unwrap();
+        assert_eq!(leader.next_slot(), Some(315_482_400));
+        assert_eq!(leader.next_run(), 3);
+    }
+
+    #[test]
+    fn block_production_history_is_saved_when_an_epoch_closes() {
+        let http = batch_responses(&[&[
+            fixture_with_id(include_str!("../fixtures/get_epoch_info.json"), 1),
+            fixture_with_id(include_str!("../fixtures/get_block_production.json"), 2),
+        ]]);
+        let store = StateStore::new();
+        store.update(|s| s.validator.value = Some(crate::simulator::sample_validator()));
+
+        // saved before a restart, up to epoch 729
+        let mut saved = crate::simulator::sample_production_history();
+        saved.epochs.pop();
+        let mut storage = MemoryStorage::new();
+        storage.save(PRODUCTION_KEY, &saved.to_bytes()).unwrap();
+        let mut fetcher =
+            Fetcher::new(http, store.clone(), &wallets(1), &[Source::BlockProduction])
+                .with_storage(storage.clone());
+        assert_eq!(store.snapshot().block_production.value, Some(saved));
+
+        fetcher.poll(Instant::now());
+        let history = store.snapshot().block_production.value.unwrap();
+        assert_eq!(history, crate::simulator::sample_production_history());
+        let stored = storage.get(PRODUCTION_KEY).unwrap();
+        assert_eq!(ProductionHistory::from_bytes(&stored).unwrap(), history);
+    }
+
+    #[test]
+    fn stake_accounts_are_found_by_staker_and_withdrawer() {
+        let accounts = include_str!("../fixtures/get_program_accounts_stake.json");
+        let http = batch_responses(&[&[
+            fixture_with_id(include_str!("../fixtures/get_epoch_info.json"), 1),
+            fixture_with_id(accounts, 2),
+            fixture_with_id(accounts, 3),
+        ]]);
+        let store = StateStore::new();
+        let mut fetcher = Fetcher::new(http, store.clone(), &wallets(1), &[Source::Stake]);
+
+        fetcher.poll(Instant::now());
+        assert_eq!(
+            store.snapshot().stake.value,
+            Some(crate::simulator::sample_stake_accounts())
+        );
+        let batch = sent_batch(&fetcher, 0);
+        let offsets: Vec<&serde_json::Value> = batch.as_array().unwrap()[1..]
+            .iter()
+            .map(|request| &request["params"][1]["filters"][1]["memcmp"]["offset"])
+            .collect();
+        assert_eq!(offsets, [STAKER_OFFSET, WITHDRAWER_OFFSET]);
+    }
+
+    #[test]
+    fn rewards_are_fetched_once_per_epoch() {
+        let http = batch_responses(&[&[fixture_with_id(
+            include_str!("../fixtures/get_inflation_reward.json"),
+            1,
+        )]]);
+        let store = StateStore::new();
+        store.update(|s| s.stake.value = Some(crate::simulator::sample_stake_accounts()));
+
+        // saved before a restart, up to epoch 728
+        let mut saved = crate::simulator::sample_reward_history();
+        saved.epochs.pop();
+        let mut storage = MemoryStorage::new();
+        storage.save(REWARDS_KEY, &saved.to_bytes()).unwrap();
+        let mut fetcher = Fetcher::new(http, store.clone(), &wallets(1), &[Source::Rewards])
+            .with_storage(storage.clone());
+        assert_eq!(store.snapshot().rewards.value, Some(saved));
+
+        let next = fetcher.poll(Instant::now()).unwrap();
+        let history = store.snapshot().rewards.value.unwrap();
+        assert_eq!(history, crate::simulator::sample_reward_history());
+        let stored = storage.get(REWARDS_KEY).unwrap();
+        assert_eq!(RewardHistory::from_bytes(&stored).unwrap(), history);
+
+        let batch = sent_batch(&fetcher, 0);
+        assert_eq!(batch[0]["params"][0].as_array().unwrap().len(), 4);
+        assert_eq!(batch[0]["params"][1]["epoch"], 729);
+
+        // nothing to ask until the stake accounts reach epoch 731
+        fetcher.poll(next);
+        assert_eq!(fetcher.http().transport().requests().len(), 1);
+    }
+
+    #[test]
+    fn lst_rates_come_from_the_pool_state_accounts() {
+        let http = batch_responses(&[&[
+            fixture_with_id(include_str!("../fixtures/marinade_state.json"), 1),
+            fixture_with_id(include_str!("../fixtures/stake_pool_jitosol.json"), 2),
+            fixture_with_id(include_str!("../fixtures/stake_pool_bsol.json"), 3),
+        ]]);
+        let store = StateStore::new();
+        let mut fetcher = Fetcher::new(http, store.clone(), &wallets(1), &[Source::LstRates]);
+
+        fetcher.poll(Instant::now());
+        let rates = store.snapshot().lst_rates.value.unwrap();
+        let sample = crate::simulator::sample_lst_rates();
+        assert_eq!(rates.len(), sample.len());
+        for (rate, sample) in rates.iter().zip(&sample) {
+            assert_eq!(rate.mint, sample.mint);
+            assert!((rate.sol - sample.sol).abs() < 1e-9);
+        }
+        let batch = sent_batch(&fetcher, 0);
+        let accounts: Vec<&str> = batch
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|request| request["params"][0].as_str().unwrap())
+            .collect();
+        assert_eq!(accounts, LSTS.map(|lst| lst.state));
+    }
+
+    #[test]
+    fn nothing_to_poll() {
+        let http = Http::init(MockTransport::new(), "https://rpc.example.com");
+        let mut fetcher = Fetcher::new(http, StateStore::new(), &wallets(1), &[]);
+        assert_eq!(fetcher.poll(Instant::now()), None);
+    }
+}
@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use crate::http::LAMPORTS_PER_SOL;
+
+/// Shown instead of a value that was never fetched successfully.
+pub const MISSING: &str = "--";
+
+pub fn lamports_to_sol(lamports: u64) -> f64 {
+    lamports as f64 / LAMPORTS_PER_SOL as f64
+}
+
+pub fn format_sol(lamports: u64) -> String {
+    format!("{:.2}", lamports_to_sol(lamports))
+}
+
+/// Token amount in at most about 7 characters: `1250.75`, `25.4k`, `3.1M`.
+pub fn format_amount(amount: f64) -> String {
+    match amount {
+        a if a < 10_000.0 => format!("{:.2}", a),
+        a if a < 1_000_000.0 => format!("{:.1}k", a / 1e3),
+        a if a < 1_000_000_000.0 => format!("{:.1}M", a / 1e6),
+        a => format!("{:.1}B", a / 1e9),
+    }
+}
+
+pub fn format_date(year: i64, month: i64, day: i64) -> String {
+    format!("{}-{:02}-{:02}", year, month, day)
+}
+
+pub fn format_time(hour: i64, minute: i64, seconds: i64) -> String {
+    format!("{:02}:{:02}:{:02}", hour, minute, seconds)
+}
+
+/// UTC time of day of a unix timestamp: `09:05:03 UTC`.
+pub fn format_utc_time(unix_time: i64) -> String {
+    let secs = unix_time.rem_euclid(86_400);
+    format!(
+        "{} UTC",
+        format_time(secs / 3_600, secs / 60 % 60, secs % 60)
+    )
+}
+
+/// Compact age for the stale marker: `45s`, `12m`, `3h`, `2d`.
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3_599 => format!("{}m", secs / 60),
+        3_600..=86_399 => format!("{}h", secs / 3_600),
+        _ => format!("{}d", secs / 86_400),
+    }
+}
+
+/// Time left in the two largest units: `2d 4h`, `18h 37m`, `12m`.
+pub fn format_eta(eta: Duration) -> String {
+    let minutes = eta.as_secs() / 60;
+    let (days, hours, minutes) = (minutes / 1_440, minutes / 60 % 24, minutes % 60);
+    match (days, hours) {
+        (0, 0) => format!("{}m", minutes),
+        (0, _) => format!("{}h {}m", hours, minutes),
+        _ => format!("{}d {}h", days, hours),
+    }
+}
+
+/// Countdown down to the second under an hour: `42s`, `2m 05s`, else like
+/// [`format_eta`].
+pub fn format_countdown(countdown: Duration) -> String {
+    let secs = countdown.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3_599 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format_eta(countdown),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sol_amounts_are_rounded_to_two_decimals() {
+        assert_eq!(format_sol(0), "0.00");
+        assert_eq!(format_sol(1_234_567_890), "1.23");
+        assert_eq!(format_sol(5_000_000), "0.01");
+        assert_eq!(format_sol(42 * LAMPORTS_PER_SOL as u64), "42.00");
+    }
+
+    #[test]
+    fn token_amounts_stay_short() {
+        assert_eq!(format_amount(0.0), "0.00");
+        assert_eq!(format_amount(1_250.75), "1250.75");
+        assert_eq!(format_amount(25_432.1), "25.4k");
+        assert_eq!(format_amount(3_140_000.0), "3.1M");
+        assert_eq!(format_amount(12_500_000.0), "12.5M");
+        assert_eq!(format_amount(98_000_000_000.0), "98.0B");
+    }
+
+    #[test]
+    fn ages_use_the_largest_whole_unit() {
+        assert_eq!(format_age(Duration::from_secs(0)), "0s");
+        assert_eq!(format_age(Duration::from_secs(59)), "59s");
+        assert_eq!(format_age(Duration::from_secs(150)), "2m");
+        assert_eq!(format_age(Duration::from_secs(7_200)), "2h");
+        assert_eq!(format_age(Duration::from_secs(200_000)), "2d");
+    }
+
+    #[test]
+    fn etas_use_the_two_largest_units() {
+        assert_eq!(format_eta(Duration::from_secs(59)), "0m");
+        assert_eq!(format_eta(Duration::from_secs(67_071)), "18h 37m");
+        assert_eq!(format_eta(Duration::from_secs(3 * 86_400 + 7_300)), "3d 2h");
+    }
+
+    #[test]
+    fn countdowns_keep_the_seconds_under_an_hour() {
+        assert_eq!(format_countdown(Duration::from_millis(42_900)), "42s");
+        assert_eq!(format_countdown(Duration::from_secs(125)), "2m 05s");
+        assert_eq!(format_countdown(Duration::from_secs(4_000)), "1h 6m");
+    }
+
+    #[test]
+    fn date_and_time_are_zero_padded() {
+        assert_eq!(format_date(2025, 1, 7), "2025-01-07");
+        assert_eq!(format_time(9, 5, 3), "09:05:03");
+        assert_eq!(format_utc_time(1_736_240_703), "09:05:03 UTC");
+    }
+}
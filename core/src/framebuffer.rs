@@ -0,0 +1,150 @@
+use core::convert::Infallible;
+use embedded_graphics::{
+    pixelcolor::BinaryColor,
+    prelude::{DrawTarget, OriginDimensions, Pixel, Size},
+};
+
+use crate::display::{Flush, DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+/// In-memory stand-in for the 128x64 SSD1306 panel.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Framebuffer {
+    pixels: Vec<bool>,
+}
+
+impl Framebuffer {
+    pub fn new() -> Self {
+        Self {
+            pixels: vec![false; DISPLAY_WIDTH * DISPLAY_HEIGHT],
+        }
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        self.pixels[y * DISPLAY_WIDTH + x]
+    }
+
+    /// Number of pixels that differ between both buffers.
+    pub fn diff(&self, other: &Framebuffer) -> usize {
+        self.pixels
+            .iter()
+            .zip(&other.pixels)
+            .filter(|(a, b)| a != b)
+            .count()
+    }
+
+    /// Encode the buffer as a grayscale PNG, each panel pixel drawn as a
+    /// `scale` x `scale` square.
+    #[cfg(feature = "simulator")]
+    pub fn to_png(&self, scale: usize) -> Result<Vec<u8>, png::EncodingError> {
+        let width = DISPLAY_WIDTH * scale;
+        let height = DISPLAY_HEIGHT * scale;
+        let mut data = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                data.push(if self.pixel(x / scale, y / scale) {
+                    0xff
+                } else {
+                    0x00
+                });
+            }
+        }
+
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)?;
+        writer.finish()?;
+        Ok(bytes)
+    }
+
+    /// Decode a PNG written by [`Framebuffer::to_png`] with a scale of 1.
+    #[cfg(feature = "simulator")]
+    pub fn from_png(bytes: &[u8]) -> Result<Self, png::DecodingError> {
+        let mut decoder = png::Decoder::new(std::io::Cursor::new(bytes));
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let mut reader = decoder.read_info()?;
+        let mut data = vec![0; reader.output_buffer_size().unwrap_or_default()];
+        let info = reader.next_frame(&mut data)?;
+        let channels = info.color_type.samples();
+
+        let mut framebuffer = Self::new();
+        let width = (info.width as usize).min(DISPLAY_WIDTH);
+        let height = (info.height as usize).min(DISPLAY_HEIGHT);
+        for y in 0..height {
+            for x in 0..width {
+                let offset = y * info.line_size + x * channels;
+                framebuffer.pixels[y * DISPLAY_WIDTH + x] = data[offset] >= 0x80;
+            }
+        }
+        Ok(framebuffer)
+    }
+}
+
+impl Default for Framebuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OriginDimensions for Framebuffer {
+    fn size(&self) -> Size {
+        Size::new(DISPLAY_WIDTH as u32, DISPLAY_HEIGHT as u32)
+    }
+}
+
+impl DrawTarget for Framebuffer {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            let (x, y) = (point.x as usize, point.y as usize);
+            if point.x >= 0 && point.y >= 0 && x < DISPLAY_WIDTH && y < DISPLAY_HEIGHT {
+                self.pixels[y * DISPLAY_WIDTH + x] = color.is_on();
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Flush for Framebuffer {
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_graphics::prelude::Point;
+
+    #[test]
+    fn out_of_bounds_pixels_are_clipped() {
+        let mut framebuffer = Framebuffer::new();
+        framebuffer
+            .draw_iter([
+                Pixel(Point::new(-1, 0), BinaryColor::On),
+                Pixel(Point::new(128, 63), BinaryColor::On),
+                Pixel(Point::new(127, 63), BinaryColor::On),
+            ])
+            .unwrap();
+        assert!(framebuffer.pixel(127, 63));
+        assert_eq!(framebuffer.diff(&Framebuffer::new()), 1);
+    }
+
+    #[cfg(feature = "simulator")]
+    #[test]
+    fn png_round_trip() {
+        let mut framebuffer = Framebuffer::new();
+        framebuffer
+            .draw_iter([Pixel(Point::new(3, 5), BinaryColor::On)])
+            .unwrap();
+        let decoded = Framebuffer::from_png(&framebuffer.to_png(1).unwrap()).unwrap();
+        assert!(decoded == framebuffer);
+    }
+}
@@ -0,0 +1,68 @@
+//! Recent values kept on the device, for the charts.
+
+/// SOL prices kept for the sparkline, two hours at the default price interval.
+pub const PRICE_HISTORY_LEN: usize = 120;
+
+/// The last `N` values pushed, oldest first. Once full every push drops the
+/// oldest value, so it never allocates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RingBuffer<T, const N: usize> {
+    items: [T; N],
+    start: usize,
+    len: usize,
+}
+
+impl<T: Copy + Default, const N: usize> Default for RingBuffer<T, N> {
+    fn default() -> Self {
+        Self {
+            items: [T::default(); N],
+            start: 0,
+            len: 0,
+        }
+    }
+}
+
+impl<T: Copy + Default, const N: usize> RingBuffer<T, N> {
+    pub fn push(&mut self, value: T) {
+        if self.len < N {
+            self.items[(self.start + self.len) % N] = value;
+            self.len += 1;
+        } else {
+            self.items[self.start] = value;
+            self.start = (self.start + 1) % N;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Values from the oldest to the latest.
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        (0..self.len).map(|i| self.items[(self.start + i) % N])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_latest_values_in_order() {
+        let mut history = RingBuffer::<u32, 3>::default();
+        assert!(history.is_empty());
+        history.push(1);
+        history.push(2);
+        assert_eq!(history.iter().collect::<Vec<_>>(), [1, 2]);
+
+        for value in 3..=5 {
+            history.push(value);
+        }
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.iter().collect::<Vec<_>>(), [3, 4, 5]);
+    }
+}
@@ -0,0 +1,277 @@
+use core::str;
+use serde_json::Value;
+use std::{error::Error, time::Duration};
+
+use crate::{
+    format::{format_date, format_time},
+    rpc::PerformanceSample,
+    state::Performance,
+    transport::{Method, Transport},
+};
+
+pub const LAMPORTS_PER_SOL: u32 = 1_000_000_000;
+
+/// Performance samples averaged into the TPS, one sample covers about a minute.
+pub const DEFAULT_TPS_SAMPLES: usize = 5;
+
+pub struct Http<T> {
+    pub(crate) sol_endpoint: String,
+    pub(crate) transport: T,
+    last_rpc_id: u64,
+}
+
+impl<T: Transport> Http<T> {
+    pub fn init(transport: T, endpoint: &str) -> Self {
+        Self {
+            sol_endpoint: endpoint.to_string(),
+            transport,
+            last_rpc_id: 0,
+        }
+    }
+
+    pub(crate) fn next_rpc_id(&mut self) -> u64 {
+        self.last_rpc_id += 1;
+        self.last_rpc_id
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    pub fn http_request(
+        &mut self,
+        method: Method,
+        uri: &str,
+        headers: &[(&str, &str)],
+        payload: Option<&str>,
+    ) -> Result<Value, Box<dyn Error>> {
+        let response = self.transport.request(
+            method,
+            uri,
+            headers,
+            payload.map(|payload_str| payload_str.as_bytes()),
+        )?;
+        let status = response.status;
+
+        println!("Response code: {}\n", status);
+        if !(200..=299).contains(&status) {
+            return Err(format!("HTTP Error: Status code {}", status).into());
+        }
+
+        let response_body = str::from_utf8(&response.body)?;
+        println!("Raw response body: {}", response_body);
+        // deserialize the response JSON
+        let json_response: Value = serde_json::from_str(response_body)?;
+
+        // result
+        Ok(json_response)
+    }
+
+    /// Slot and TPS averaged over the last `samples` performance samples.
+    pub fn get_tps(&mut self, samples: usize) -> Result<Performance, Box<dyn Error>> {
+        let samples = self.get_recent_performance_samples(samples)?;
+        parse_tps(&samples)
+    }
+
+    pub fn utc_offset_time(&mut self) -> Result<(String, String), Box<dyn Error>> {
+        let headers = [("accept", "application/json")];
+        let url = "https://timeapi.io/api/time/current/zone?timeZone=America/Bogota";
+        let response = self.http_request(Method::Get, url, &headers, None)?;
+        parse_time(&response)
+    }
+}
+
+/// Latest slot plus the total and non-vote TPS over all `samples`, i.e. the
+/// transactions of every sample divided by the seconds they cover. The
+/// non-vote TPS is `None` when a node does not report it for every sample.
+/// The slot time is the same seconds divided by the slots they cover.
+pub fn parse_tps(samples: &[PerformanceSample]) -> Result<Performance, Box<dyn Error>> {
+    let slot = samples
+        .iter()
+        .map(|sample| sample.slot)
+        .max()
+        .ok_or("no performance samples found in the response")?;
+
+    let samples = samples
+        .iter()
+        .filter(|sample| sample.sample_period_secs > 0);
+    let secs: u64 = samples
+        .clone()
+        .map(|sample| sample.sample_period_secs as u64)
+        .sum();
+    if secs == 0 {
+        return Err("the performance samples cover no time".into());
+    }
+    let per_second = |transactions: u64| (transactions + secs / 2) / secs;
+
+    let slots: u64 = samples.clone().map(|sample| sample.num_slots).sum();
+    let total = samples.clone().map(|sample| sample.num_transactions).sum();
+    let non_vote = samples
+        .map(|sample| sample.num_non_vote_transactions)
+        .sum::<Option<u64>>();
+    Ok(Performance {
+        slot,
+        tps: per_second(total),
+        non_vote_tps: non_vote.map(per_second),
+        slot_time: (slots > 0).then(|| Duration::from_millis(secs * 1_000 / slots)),
+    })
+}
+
+/// timeapi.io response -> `(time, date)`.
+pub fn parse_time(response: &Value) -> Result<(String, String), Box<dyn Error>> {
+    let field = |name: &str| {
+        response[name]
+            .as_i64()
+            .ok_or_else(|| format!("no {} found in the time response", name))
+    };
+    let year = field("year")?;
+    let month = field("month")?;
+    let day = field("day")?;
+    let hour = field("hour")?;
+    let minute = field("minute")?;
+    let seconds = field("seconds")?;
+
+    Ok((
+        format_time(hour, minute, seconds),
+        format_date(year, month, day),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockTransport;
+    use serde_json::json;
+
+    const RPC: &str = "https://rpc.example.com";
+
+    fn http(transport: MockTransport) -> Http<MockTransport> {
+        Http::init(transport, RPC)
+    }
+
+    #[test]
+    fn get_balance_posts_a_json_rpc_request() {
+        let mut http =
+            http(MockTransport::new().respond(200, include_str!("../fixtures/get_balance.json")));
+        assert_eq!(http.get_balance("wallet").unwrap(), 12_345_678_901);
+
+        let request = &http.transport().requests()[0];
+        assert_eq!(request.method, Method::Post);
+        assert_eq!(request.uri, RPC);
+        let body: Value = serde_json::from_slice(request.body.as_deref().unwrap()).unwrap();
+        assert_eq!(body["method"], "getBalance");
+        assert_eq!(body["params"], json!(["wallet"]));
+    }
+
+    #[test]
+    fn get_tps_from_recorded_samples() {
+        let mut http = http(MockTransport::new().respond(
+            200,
+            include_str!("../fixtures/get_recent_performance_samples.json"),
+        ));
+        assert_eq!(
+            http.get_tps(1).unwrap(),
+            Performance {
+                slot: 315_482_100,
+                tps: 4_090,
+                non_vote_tps: Some(1_039),
+                slot_time: Some(Duration::from_millis(400)),
+            }
+        );
+    }
+
+    #[test]
+    fn tps_averages_every_sample_over_its_period() {
+        let sample = |slot, num_transactions, non_vote, sample_period_secs| PerformanceSample {
+            slot,
+            num_slots: 150,
+            num_transactions,
+            num_non_vote_transactions: non_vote,
+            sample_period_secs,
+        };
+
+        // 9000 transactions over 90 seconds, the sample without a period is
+        // skipped rather than counted over no time
+        let samples = [
+            sample(300, 3_000, Some(900), 30),
+            sample(150, 6_000, Some(1_200), 60),
+            sample(0, 50_000, Some(9_000), 0),
+        ];
+        assert_eq!(
+            parse_tps(&samples).unwrap(),
+            Performance {
+                slot: 300,
+                tps: 100,
+                non_vote_tps: Some(23),
+                slot_time: Some(Duration::from_millis(300)),
+            }
+        );
+
+        // an older node that does not report non-vote transactions
+        let samples = [
+            sample(300, 3_000, Some(900), 30),
+            sample(150, 6_000, None, 60),
+        ];
+        assert_eq!(parse_tps(&samples).unwrap().non_vote_tps, None);
+
+        assert!(parse_tps(&[]).is_err());
+        assert!(parse_tps(&[sample(1, 10, None, 0)]).is_err());
+    }
+
+    #[test]
+    fn get_time() {
+        let mut http = http(
+            MockTransport::new()
+                .respond(200, include_str!("../fixtures/timeapi_current_zone.json")),
+        );
+        assert_eq!(
+            http.utc_offset_time().unwrap(),
+            ("09:05:03".to_string(), "2025-01-07".to_string())
+        );
+        assert_eq!(http.transport().requests()[0].method, Method::Get);
+        assert_eq!(http.transport().remaining(), 0);
+    }
+
+    #[test]
+    fn failures_are_reported() {
+        let mut http = http(
+            MockTransport::new()
+                .respond(429, include_str!("../fixtures/rpc_rate_limited.json"))
+                .fail("connection reset")
+                .respond(200, vec![0xff, 0xfe]),
+        );
+        assert!(http
+            .get_balance("wallet")
+            .unwrap_err()
+            .to_string()
+            .starts_with("RPC error 429: Too many requests"));
+        assert_eq!(http.get_tps(1).unwrap_err().to_string(), "connection reset");
+        assert!(http.utc_offset_time().is_err());
+    }
+
+    #[test]
+    fn missing_fields_are_errors_not_zeros() {
+        let mut http = http(
+            MockTransport::new()
+                .respond(200, include_str!("../fixtures/rpc_rate_limited.json"))
+                .respond(200, "{\"year\": 2025}"),
+        );
+        assert!(http.get_balance("wallet").is_err());
+        assert_eq!(
+            http.utc_offset_time().unwrap_err().to_string(),
+            "no month found in the time response"
+        );
+    }
+
+    #[test]
+    fn time_responses() {
+        let time = json!({
+            "year": 2025, "month": 2, "day": 3,
+            "hour": 7, "minute": 8, "seconds": 9
+        });
+        assert_eq!(
+            parse_time(&time).unwrap(),
+            ("07:08:09".to_string(), "2025-02-03".to_string())
+        );
+    }
+}
@@ -0,0 +1,133 @@
+//! When the watched validator is next leader.
+//!
+//! `getLeaderSchedule` gives the leader slots of the validator identity for
+//! the whole epoch, so it is fetched once per epoch while `getSlot` moves the
+//! countdown along. Leaders are scheduled in runs of [`LEADER_RUN_SLOTS`]
+//! consecutive slots.
+
+use std::time::Duration;
+
+use crate::rpc::{EpochInfo, LeaderSlots};
+
+/// Consecutive slots a leader gets in a row.
+pub const LEADER_RUN_SLOTS: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeaderSchedule {
+    pub epoch: u64,
+    pub first_slot: u64,
+    pub last_slot: u64,
+    /// Leader slots of the identity in the epoch, ascending.
+    pub slots: Vec<u64>,
+    /// Latest slot of the cluster.
+    pub slot: u64,
+}
+
+impl LeaderSchedule {
+    /// The leader slots of `identity` in `schedule`, which is relative to the
+    /// first slot of `epoch`.
+    pub fn new(identity: &str, epoch: &EpochInfo, schedule: LeaderSlots) -> Self {
+        let first_slot = epoch.absolute_slot - epoch.slot_index;
+        let mut slots: Vec<u64> = schedule
+            .and_then(|mut schedule| schedule.remove(identity))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|index| first_slot + index)
+            .collect();
+        slots.sort_unstable();
+        Self {
+            epoch: epoch.epoch,
+            first_slot,
+            last_slot: first_slot + epoch.slots_in_epoch.saturating_sub(1),
+            slots,
+            slot: epoch.absolute_slot,
+        }
+    }
+
+    /// Whether the latest slot is still in the epoch of this schedule.
+    pub fn is_current(&self) -> bool {
+        (self.first_slot..=self.last_slot).contains(&self.slot)
+    }
+
+    /// Leader slots from the latest slot on.
+    pub fn upcoming(&self) -> &[u64] {
+        let start = self.slots.partition_point(|&slot| slot < self.slot);
+        &self.slots[start..]
+    }
+
+    pub fn next_slot(&self) -> Option<u64> {
+        self.upcoming().first().copied()
+    }
+
+    /// Length of the run of consecutive leader slots starting at the next one.
+    pub fn next_run(&self) -> usize {
+        let upcoming = self.upcoming();
+        let consecutive = upcoming.windows(2).take_while(|w| w[1] == w[0] + 1);
+        upcoming.len().min(consecutive.count() + 1)
+    }
+
+    /// Time until the next leader slot if every slot takes `slot_time`.
+    pub fn time_until_next(&self, slot_time: Duration) -> Option<Duration> {
+        let slots = self.next_slot()? - self.slot;
+        Some(slot_time * slots.min(u32::MAX as u64) as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::{sample_leader_schedule, SAMPLE_VOTE_IDENTITY};
+
+    fn epoch() -> EpochInfo {
+        let response: serde_json::Value =
+            serde_json::from_str(include_str!("../fixtures/get_epoch_info.json")).unwrap();
+        serde_json::from_value(response["result"].clone()).unwrap()
+    }
+
+    fn leader_slots() -> LeaderSlots {
+        let response: serde_json::Value =
+            serde_json::from_str(include_str!("../fixtures/get_leader_schedule.json")).unwrap();
+        serde_json::from_value(response["result"].clone()).unwrap()
+    }
+
+    #[test]
+    fn schedule_slots_are_made_absolute() {
+        let schedule = LeaderSchedule::new(SAMPLE_VOTE_IDENTITY, &epoch(), leader_slots());
+        assert_eq!(schedule, sample_leader_schedule());
+        assert_eq!(schedule.first_slot, 315_217_779);
+        assert_eq!(schedule.last_slot, 315_649_778);
+        assert_eq!(schedule.slots.len(), 48);
+        assert!(schedule.is_current());
+
+        let other = LeaderSchedule::new("unknown", &epoch(), leader_slots());
+        assert!(other.slots.is_empty());
+        assert_eq!(
+            LeaderSchedule::new("unknown", &epoch(), None).next_slot(),
+            None
+        );
+    }
+
+    #[test]
+    fn countdown_to_the_next_leader_slots() {
+        let mut schedule = sample_leader_schedule();
+        assert_eq!(schedule.next_slot(), Some(315_482_399));
+        assert_eq!(schedule.next_run(), 4);
+        assert_eq!(schedule.upcoming().len(), 24);
+        assert_eq!(
+            schedule.time_until_next(Duration::from_millis(400)),
+            Some(Duration::from_millis(119_600))
+        );
+
+        // in the middle of a run
+        schedule.slot = 315_482_401;
+        assert_eq!(schedule.next_slot(), Some(315_482_401));
+        assert_eq!(schedule.next_run(), 2);
+        assert_eq!(schedule.upcoming().len(), 22);
+
+        // the epoch is over
+        schedule.slot = schedule.last_slot + 1;
+        assert!(!schedule.is_current());
+        assert_eq!(schedule.next_slot(), None);
+        assert_eq!(schedule.next_run(), 0);
+    }
+}
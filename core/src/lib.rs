@@ -0,0 +1,34 @@
+//! Platform-independent part of the esp32-ssd1306-solana firmware.
+//!
+//! Everything in here builds on the host so the Solana parsing and the screen
+//! layouts can be tested with a plain `cargo test`. The ESP binary provides the
+//! HTTP [`transport::Transport`] and the SSD1306 draw target.
+
+pub mod alerts;
+pub mod currency;
+pub mod display;
+pub mod fetcher;
+pub mod format;
+pub mod framebuffer;
+pub mod history;
+pub mod http;
+pub mod leader;
+pub mod lst;
+pub mod mock;
+pub mod playlist;
+pub mod portfolio;
+pub mod price;
+pub mod production;
+pub mod pyth;
+mod reader;
+pub mod rewards;
+pub mod rpc;
+pub mod screen;
+pub mod simulator;
+pub mod stake;
+pub mod state;
+pub mod storage;
+pub mod tokens;
+pub mod transport;
+pub mod validator;
+pub mod wallets;
@@ -0,0 +1,283 @@
+//! Liquid staking tokens and what they are worth in SOL.
+//!
+//! An LST is backed by the stake of its pool, so its SOL value comes from the
+//! pool's state account rather than a market price. jitoSOL and bSOL are SPL
+//! stake pools, where the rate is the pool's lamports over its token supply.
+//! mSOL is Marinade's, which keeps the price itself as a fixed point number.
+
+use std::error::Error;
+
+use crate::{
+    reader::Reader,
+    rpc::{AccountInfo, TokenAccount},
+    tokens::{Token, TokenBalance},
+};
+
+pub const STAKE_POOL_PROGRAM_ID: &str = "SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy";
+pub const MARINADE_PROGRAM_ID: &str = "MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD";
+
+/// First 8 bytes of `sha256("account:State")`.
+const MARINADE_STATE_DISCRIMINATOR: [u8; 8] = [216, 146, 107, 94, 104, 75, 182, 177];
+
+/// `msol_price` is SOL per mSOL times this.
+const MARINADE_PRICE_DENOMINATOR: f64 = 4_294_967_296.0;
+
+/// Offsets of the fields read from the state accounts.
+const MARINADE_PRICE_OFFSET: usize = 512;
+const STAKE_POOL_TOTALS_OFFSET: usize = 258;
+
+/// `AccountType::StakePool` of an SPL stake pool account.
+const STAKE_POOL_ACCOUNT_TYPE: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LstPool {
+    Marinade,
+    StakePool,
+}
+
+/// A liquid staking token and the account its rate is read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lst {
+    pub symbol: &'static str,
+    pub mint: &'static str,
+    pub state: &'static str,
+    pub pool: LstPool,
+}
+
+pub const LSTS: [Lst; 3] = [
+    Lst {
+        symbol: "mSOL",
+        mint: "mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So",
+        state: "8szGkuLTAux9XMgZ2vtY39jVSowEcpBfFfD8hXSEqdGC",
+        pool: LstPool::Marinade,
+    },
+    Lst {
+        symbol: "jitoSOL",
+        mint: "J1toso1uCk3RLmjorhTtrVwY9HJ7X8V9yYac6Y7kGCPn",
+        state: "Jito4APyf642JPZPx3hGc6WWJ8zPKtRbRs4P815Awbb",
+        pool: LstPool::StakePool,
+    },
+    Lst {
+        symbol: "bSOL",
+        mint: "bSo13r4TkiE4KumL71LsHTPpL2euBYLFx6h9HP3piy1",
+        state: "stk9ApL5HeVAwPLr3TLhDXdZS8ptVu7zp6ov8HFDuMi",
+        pool: LstPool::StakePool,
+    },
+];
+
+/// The known LST of `mint`.
+pub fn find(mint: &str) -> Option<&'static Lst> {
+    LSTS.iter().find(|lst| lst.mint == mint)
+}
+
+/// SOL one token of `mint` can be redeemed for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LstRate {
+    pub mint: String,
+    pub sol: f64,
+}
+
+impl Lst {
+    /// Rate decoded from the state account of the pool.
+    pub fn rate(&self, account: &AccountInfo) -> Result<LstRate, Box<dyn Error>> {
+        let data = account.decode_data()?;
+        let sol = match self.pool {
+            LstPool::Marinade => {
+                if account.owner != MARINADE_PROGRAM_ID {
+                    return Err(format!("{} is not owned by Marinade", self.state).into());
+                }
+                decode_marinade_price(&data)?
+            }
+            LstPool::StakePool => {
+                if account.owner != STAKE_POOL_PROGRAM_ID {
+                    return Err(format!("{} is not a stake pool", self.state).into());
+                }
+                decode_stake_pool_rate(&data)?
+            }
+        };
+        Ok(LstRate {
+            mint: self.mint.to_string(),
+            sol,
+        })
+    }
+}
+
+/// SOL per mSOL from Marinade's `State` account.
+fn decode_marinade_price(data: &[u8]) -> Result<f64, Box<dyn Error>> {
+    let mut reader = Reader::new(data, "Marinade state");
+    if reader.take::<8>()? != MARINADE_STATE_DISCRIMINATOR {
+        return Err("not a Marinade state account".into());
+    }
+    reader.skip(MARINADE_PRICE_OFFSET - 8)?;
+    Ok(reader.u64()? as f64 / MARINADE_PRICE_DENOMINATOR)
+}
+
+/// SOL per pool token from an SPL `StakePool` account: the lamports the
+/// pool manages over the pool tokens minted.
+fn decode_stake_pool_rate(data: &[u8]) -> Result<f64, Box<dyn Error>> {
+    let mut reader = Reader::new(data, "stake pool");
+    if reader.u8()? != STAKE_POOL_ACCOUNT_TYPE {
+        return Err("not an initialized stake pool".into());
+    }
+    reader.skip(STAKE_POOL_TOTALS_OFFSET - 1)?;
+    let total_lamports = reader.u64()?;
+    let pool_token_supply = reader.u64()?;
+    if pool_token_supply == 0 {
+        return Err("the stake pool has no tokens".into());
+    }
+    Ok(total_lamports as f64 / pool_token_supply as f64)
+}
+
+/// The balance in SOL when it is an LST with a known rate.
+pub fn sol_value(balance: &TokenBalance, rates: &[LstRate]) -> Option<f64> {
+    let rate = rates.iter().find(|rate| rate.mint == balance.mint)?;
+    Some(balance.ui_amount() * rate.sol)
+}
+
+/// LSTs held by one wallet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LstHolding {
+    pub owner: String,
+    pub balance: TokenBalance,
+}
+
+/// Sum the LST token `accounts` per owner and mint. With an allowlist only
+/// the allowlisted LSTs count, as on the portfolio page.
+pub fn lst_holdings(
+    allowlist: &[Token],
+    accounts: &[TokenAccount],
+) -> Result<Vec<LstHolding>, Box<dyn Error>> {
+    let mut holdings: Vec<LstHolding> = Vec::new();
+    for account in accounts {
+        let info = &account.account.data.parsed.info;
+        let Some(lst) = find(&info.mint) else {
+            continue;
+        };
+        if !allowlist.is_empty() && allowlist.iter().all(|token| token.mint != lst.mint) {
+            continue;
+        }
+        let amount: u64 = info.token_amount.amount.parse().map_err(|_| {
+            format!(
+                "invalid amount {:?} in token account {}",
+                info.token_amount.amount, account.pubkey
+            )
+        })?;
+        let position = holdings
+            .iter()
+            .position(|h| h.owner == info.owner && h.balance.mint == lst.mint);
+        let holding = match position {
+            Some(i) => &mut holdings[i],
+            None => {
+                holdings.push(LstHolding {
+                    owner: info.owner.clone(),
+                    balance: TokenBalance {
+                        symbol: lst.symbol.to_string(),
+                        mint: lst.mint.to_string(),
+                        amount: 0,
+                        decimals: info.token_amount.decimals,
+                    },
+                });
+                holdings.last_mut().expect("just pushed")
+            }
+        };
+        holding.balance.amount = holding.balance.amount.saturating_add(amount);
+    }
+    Ok(holdings)
+}
+
+/// SOL value of the LSTs `owner` holds, `None` when it holds none with a
+/// known rate.
+pub fn owner_sol_value(owner: &str, holdings: &[LstHolding], rates: &[LstRate]) -> Option<f64> {
+    holdings
+        .iter()
+        .filter(|holding| holding.owner == owner)
+        .filter_map(|holding| sol_value(&holding.balance, rates))
+        .reduce(|a, b| a + b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        rpc::{RpcResponse, WithContext},
+        simulator::{sample_lst_holdings, sample_lst_rates, SAMPLE_WALLET},
+    };
+
+    fn fixture_account(fixture: &str) -> AccountInfo {
+        let response: RpcResponse<WithContext<Option<AccountInfo>>> =
+            serde_json::from_str(fixture).unwrap();
+        response.result.unwrap().value.unwrap()
+    }
+
+    fn fixture_rates() -> Vec<LstRate> {
+        let accounts = [
+            include_str!("../fixtures/marinade_state.json"),
+            include_str!("../fixtures/stake_pool_jitosol.json"),
+            include_str!("../fixtures/stake_pool_bsol.json"),
+        ];
+        LSTS.iter()
+            .zip(accounts)
+            .map(|(lst, fixture)| lst.rate(&fixture_account(fixture)).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn decodes_the_pool_rates() {
+        let rates = fixture_rates();
+        let sample = sample_lst_rates();
+        for (rate, sample) in rates.iter().zip(&sample) {
+            assert_eq!(rate.mint, sample.mint);
+            assert!((rate.sol - sample.sol).abs() < 1e-9, "{:?}", rate);
+        }
+    }
+
+    #[test]
+    fn rejects_other_accounts() {
+        let [msol, jitosol, _] = LSTS;
+        let marinade = fixture_account(include_str!("../fixtures/marinade_state.json"));
+        let pool = fixture_account(include_str!("../fixtures/stake_pool_jitosol.json"));
+        assert_eq!(
+            msol.rate(&pool).unwrap_err().to_string(),
+            "8szGkuLTAux9XMgZ2vtY39jVSowEcpBfFfD8hXSEqdGC is not owned by Marinade"
+        );
+        assert!(jitosol.rate(&marinade).is_err());
+
+        let data = marinade.decode_data().unwrap();
+        assert_eq!(
+            decode_marinade_price(&data[..300]).unwrap_err().to_string(),
+            "the Marinade state is too short"
+        );
+        let mut data = pool.decode_data().unwrap();
+        data[0] = 2;
+        assert!(decode_stake_pool_rate(&data).is_err());
+    }
+
+    #[test]
+    fn holdings_are_valued_per_wallet() {
+        let response: RpcResponse<WithContext<Vec<TokenAccount>>> = serde_json::from_str(
+            include_str!("../fixtures/get_token_accounts_by_owner_lst.json"),
+        )
+        .unwrap();
+        // the USDC account is not an LST, the two mSOL accounts add up
+        let accounts = response.result.unwrap().value;
+        let holdings = lst_holdings(&[], &accounts).unwrap();
+        assert_eq!(holdings, sample_lst_holdings());
+
+        // with an allowlist the other LSTs are left out, as on the portfolio
+        let msol = Token {
+            symbol: "mSOL".to_string(),
+            mint: LSTS[0].mint.to_string(),
+        };
+        let allowlisted = lst_holdings(&[msol], &accounts).unwrap();
+        assert_eq!(allowlisted, sample_lst_holdings()[..1]);
+
+        let sol = owner_sol_value(SAMPLE_WALLET, &holdings, &fixture_rates()).unwrap();
+        assert!(
+            (sol - (2.5 * 1.2845 + 1.2 * 1.1872)).abs() < 1e-6,
+            "{}",
+            sol
+        );
+        assert_eq!(owner_sol_value("other", &holdings, &fixture_rates()), None);
+        assert_eq!(owner_sol_value(SAMPLE_WALLET, &holdings, &[]), None);
+    }
+}
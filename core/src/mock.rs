@@ -0,0 +1,74 @@
+use std::{collections::VecDeque, error::Error};
+
+use crate::transport::{Method, Response, Transport};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedRequest {
+    pub method: Method,
+    pub uri: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+}
+
+/// In-memory [`Transport`] that replays a script of canned responses in order
+/// and records every request it receives.
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    script: VecDeque<Result<Response, String>>,
+    requests: Vec<RecordedRequest>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a response with the given status code and body.
+    pub fn respond(mut self, status: u16, body: impl Into<Vec<u8>>) -> Self {
+        self.script.push_back(Ok(Response {
+            status,
+            body: body.into(),
+        }));
+        self
+    }
+
+    /// Queue a transport level failure, as if the connection dropped.
+    pub fn fail(mut self, error: &str) -> Self {
+        self.script.push_back(Err(error.to_string()));
+        self
+    }
+
+    pub fn requests(&self) -> &[RecordedRequest] {
+        &self.requests
+    }
+
+    /// Number of scripted responses that were not consumed yet.
+    pub fn remaining(&self) -> usize {
+        self.script.len()
+    }
+}
+
+impl Transport for MockTransport {
+    fn request(
+        &mut self,
+        method: Method,
+        uri: &str,
+        headers: &[(&str, &str)],
+        body: Option<&[u8]>,
+    ) -> Result<Response, Box<dyn Error>> {
+        self.requests.push(RecordedRequest {
+            method,
+            uri: uri.to_string(),
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            body: body.map(<[u8]>::to_vec),
+        });
+        match self.script.pop_front() {
+            Some(Ok(response)) => Ok(response),
+            Some(Err(error)) => Err(error.into()),
+            None => Err(format!("no scripted response left for {}", uri).into()),
+        }
+    }
+}
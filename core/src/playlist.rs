@@ -0,0 +1,248 @@
+//! The `playlist` setting: which screens to show, in what order and for how long.
+//!
+//! Written as a comma separated list of screen names, each optionally followed
+//! by `=<dwell in milliseconds>`, e.g. `"balance=1500, slots, price, qr=6000"`.
+//! Screens without a dwell keep their own default. `balance` stands for one
+//! page per configured wallet, `wallets` for a single summary page of them all.
+//!
+//! [`check`] is a `const fn` so the firmware can reject a bad playlist from
+//! `cfg.toml` while compiling instead of silently skipping a screen.
+
+use core::fmt;
+use std::time::Duration;
+
+/// Every screen name the playlist accepts.
+pub const SCREEN_NAMES: &[&str] = &[
+    "balance",
+    "wallets",
+    "tokens",
+    "portfolio",
+    "slots",
+    "epoch",
+    "validator",
+    "leader",
+    "blocks",
+    "stake",
+    "rewards",
+    "price",
+    "qr",
+];
+
+pub const DEFAULT_PLAYLIST: &str = "balance, slots, price, qr";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaylistError {
+    Empty,
+    EmptyEntry,
+    UnknownScreen,
+    InvalidDwell,
+}
+
+impl PlaylistError {
+    pub const fn message(&self) -> &'static str {
+        match self {
+            PlaylistError::Empty => "the playlist has no screens",
+            PlaylistError::EmptyEntry => "the playlist has an empty entry (stray comma?)",
+            PlaylistError::UnknownScreen => "the playlist names an unknown screen",
+            PlaylistError::InvalidDwell => {
+                "a playlist dwell time is not a positive number of milliseconds"
+            }
+        }
+    }
+}
+
+impl fmt::Display for PlaylistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for PlaylistError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    pub name: &'static str,
+    pub dwell: Option<Duration>,
+}
+
+pub(crate) const fn trim(bytes: &[u8]) -> &[u8] {
+    let mut bytes = bytes;
+    while let [b' ' | b'\t' | b'\n', rest @ ..] = bytes {
+        bytes = rest;
+    }
+    while let [rest @ .., b' ' | b'\t' | b'\n'] = bytes {
+        bytes = rest;
+    }
+    bytes
+}
+
+pub(crate) const fn eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn screen_name(name: &[u8]) -> Option<&'static str> {
+    let mut i = 0;
+    while i < SCREEN_NAMES.len() {
+        if eq(name, SCREEN_NAMES[i].as_bytes()) {
+            return Some(SCREEN_NAMES[i]);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Position of `needle` in `bytes`, or `bytes.len()` when missing.
+pub(crate) const fn find(bytes: &[u8], needle: u8) -> usize {
+    let mut i = 0;
+    while i < bytes.len() && bytes[i] != needle {
+        i += 1;
+    }
+    i
+}
+
+const fn parse_millis(digits: &[u8]) -> Result<u64, PlaylistError> {
+    if digits.is_empty() {
+        return Err(PlaylistError::InvalidDwell);
+    }
+    let mut millis: u64 = 0;
+    let mut i = 0;
+    while i < digits.len() {
+        let digit = digits[i];
+        if !digit.is_ascii_digit() {
+            return Err(PlaylistError::InvalidDwell);
+        }
+        millis = match millis.checked_mul(10) {
+            Some(value) => match value.checked_add((digit - b'0') as u64) {
+                Some(value) => value,
+                None => return Err(PlaylistError::InvalidDwell),
+            },
+            None => return Err(PlaylistError::InvalidDwell),
+        };
+        i += 1;
+    }
+    if millis == 0 {
+        return Err(PlaylistError::InvalidDwell);
+    }
+    Ok(millis)
+}
+
+/// Parse one `name[=millis]` entry into `(name, millis)`.
+const fn parse_entry(entry: &[u8]) -> Result<(&'static str, Option<u64>), PlaylistError> {
+    let entry = trim(entry);
+    if entry.is_empty() {
+        return Err(PlaylistError::EmptyEntry);
+    }
+    let (name, dwell) = entry.split_at(find(entry, b'='));
+    let name = match screen_name(trim(name)) {
+        Some(name) => name,
+        None => return Err(PlaylistError::UnknownScreen),
+    };
+    match dwell {
+        [] => Ok((name, None)),
+        [_, millis @ ..] => match parse_millis(trim(millis)) {
+            Ok(millis) => Ok((name, Some(millis))),
+            Err(err) => Err(err),
+        },
+    }
+}
+
+/// Validate a playlist without allocating, usable in `const` context.
+pub const fn validate(playlist: &str) -> Result<(), PlaylistError> {
+    let mut rest = playlist.as_bytes();
+    if trim(rest).is_empty() {
+        return Err(PlaylistError::Empty);
+    }
+    loop {
+        let (entry, tail) = rest.split_at(find(rest, b','));
+        if let Err(err) = parse_entry(entry) {
+            return Err(err);
+        }
+        match tail {
+            [] => return Ok(()),
+            [_, tail @ ..] => rest = tail,
+        }
+    }
+}
+
+/// Panics with a readable message on an invalid playlist. Meant for
+/// `const _: () = playlist::check(CONFIG.playlist);` so it fails the build.
+pub const fn check(playlist: &str) {
+    if let Err(err) = validate(playlist) {
+        panic!("{}", err.message());
+    }
+}
+
+pub fn parse(playlist: &str) -> Result<Vec<Entry>, PlaylistError> {
+    validate(playlist)?;
+    playlist
+        .split(',')
+        .map(|entry| {
+            let (name, millis) = parse_entry(entry.as_bytes())?;
+            Ok(Entry {
+                name,
+                dwell: millis.map(Duration::from_millis),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // compile time validation of the default playlist
+    const _: () = check(DEFAULT_PLAYLIST);
+
+    #[test]
+    fn parses_names_and_optional_dwell() {
+        let entries = parse(" balance = 2000,slots, qr=6000 ").unwrap();
+        assert_eq!(
+            entries,
+            [
+                Entry {
+                    name: "balance",
+                    dwell: Some(Duration::from_millis(2000)),
+                },
+                Entry {
+                    name: "slots",
+                    dwell: None,
+                },
+                Entry {
+                    name: "qr",
+                    dwell: Some(Duration::from_secs(6)),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_bad_playlists() {
+        assert_eq!(validate(""), Err(PlaylistError::Empty));
+        assert_eq!(validate("balance,,qr"), Err(PlaylistError::EmptyEntry));
+        assert_eq!(validate("balance,"), Err(PlaylistError::EmptyEntry));
+        assert_eq!(validate("balnce"), Err(PlaylistError::UnknownScreen));
+        assert_eq!(validate("qr=6s"), Err(PlaylistError::InvalidDwell));
+        assert_eq!(validate("qr="), Err(PlaylistError::InvalidDwell));
+        assert_eq!(validate("qr=0"), Err(PlaylistError::InvalidDwell));
+        assert_eq!(
+            validate("qr=99999999999999999999"),
+            Err(PlaylistError::InvalidDwell)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown screen")]
+    fn check_panics_with_the_error_message() {
+        check("price,weather");
+    }
+}
@@ -0,0 +1,129 @@
+//! Fiat value of everything the wallets hold: SOL plus the SPL tokens, each
+//! multiplied by its price. Liquid staking tokens are valued at what their
+//! pool redeems them for in SOL.
+
+use crate::{format::lamports_to_sol, lst::sol_value, state::Snapshot};
+
+/// One line of the portfolio page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Holding {
+    pub symbol: String,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Portfolio {
+    /// Priced holdings, largest value first.
+    pub holdings: Vec<Holding>,
+    pub total: f64,
+}
+
+/// Value the latest snapshot. `None` until every wallet balance and the SOL
+/// price are known; tokens without a price yet are left out of the total.
+pub fn portfolio(snapshot: &Snapshot) -> Option<Portfolio> {
+    let lamports = snapshot
+        .balances
+        .iter()
+        .map(|balance| balance.value)
+        .sum::<Option<u64>>()?;
+    let sol_price = snapshot.price.value?.price;
+
+    let mut holdings = vec![Holding {
+        symbol: "SOL".to_string(),
+        value: lamports_to_sol(lamports) * sol_price,
+    }];
+    let tokens = snapshot.tokens.value.as_deref().unwrap_or_default();
+    let prices = snapshot.token_prices.value.as_deref().unwrap_or_default();
+    let rates = snapshot.lst_rates.value.as_deref().unwrap_or_default();
+    for token in tokens {
+        let value = match sol_value(token, rates) {
+            Some(sol) => sol * sol_price,
+            None => match prices.iter().find(|price| price.mint == token.mint) {
+                Some(price) => token.ui_amount() * price.price,
+                None => continue,
+            },
+        };
+        holdings.push(Holding {
+            symbol: token.symbol.clone(),
+            value,
+        });
+    }
+    holdings.sort_by(|a, b| b.value.total_cmp(&a.value));
+
+    Some(Portfolio {
+        total: holdings.iter().map(|holding| holding.value).sum(),
+        holdings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        simulator::{sample_lst_holdings, sample_snapshot},
+        state::Metric,
+        tokens::TokenPrice,
+    };
+    use std::time::Instant;
+
+    #[test]
+    fn values_sol_and_priced_tokens() {
+        let mut snapshot = sample_snapshot(Instant::now());
+        snapshot.token_prices.value = Some(vec![
+            TokenPrice {
+                mint: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string(),
+                price: 1.0,
+            },
+            TokenPrice {
+                mint: "JUPyiwrYJFskUPiHa7hkeR8VUtAeFoSYbKedZNsDvCN".to_string(),
+                price: 0.5,
+            },
+        ]);
+        let portfolio = portfolio(&snapshot).unwrap();
+
+        let holdings: Vec<_> = portfolio
+            .holdings
+            .iter()
+            .map(|h| (h.symbol.as_str(), (h.value * 100.0).round() / 100.0))
+            .collect();
+        // 15.587778901 SOL at 187.42, the unpriced tokens are left out
+        assert_eq!(
+            holdings,
+            [("SOL", 2_921.46), ("USDC", 1_250.75), ("JUP", 215.54)]
+        );
+        assert_eq!((portfolio.total * 100.0).round() / 100.0, 4_387.75);
+    }
+
+    #[test]
+    fn liquid_staking_tokens_follow_their_pool_rate() {
+        let mut snapshot = sample_snapshot(Instant::now());
+        let msol = sample_lst_holdings().remove(0).balance;
+        snapshot.tokens.value = Some(vec![msol.clone()]);
+        // the market price is ignored once the pool rate is known
+        snapshot.token_prices.value = Some(vec![TokenPrice {
+            mint: msol.mint.clone(),
+            price: 200.0,
+        }]);
+        let value = |snapshot: &Snapshot| {
+            let portfolio = portfolio(snapshot).unwrap();
+            let holding = portfolio.holdings.iter().find(|h| h.symbol == "mSOL");
+            (holding.unwrap().value * 100.0).round() / 100.0
+        };
+        // 2.5 mSOL at 1.2845 SOL, 187.42 each
+        assert_eq!(value(&snapshot), 601.85);
+
+        snapshot.lst_rates = Metric::default();
+        assert_eq!(value(&snapshot), 500.0);
+    }
+
+    #[test]
+    fn needs_every_balance_and_the_sol_price() {
+        let mut snapshot = sample_snapshot(Instant::now());
+        snapshot.balances[1] = Metric::default();
+        assert_eq!(portfolio(&snapshot), None);
+
+        let mut snapshot = sample_snapshot(Instant::now());
+        snapshot.price = Metric::default();
+        assert_eq!(portfolio(&snapshot), None);
+    }
+}
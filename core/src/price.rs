@@ -0,0 +1,645 @@
+//! Where the SOL and SPL token prices come from.
+//!
+//! Every backend implements [`PriceProvider`] and quotes in the configured
+//! [`Currency`]; the backends that only know USD fail for any other currency
+//! so the next one in line is asked. The `price_providers` setting
+//! lists the ones to use, in order of preference, e.g. `"coingecko, jupiter,
+//! pyth"`. [`PriceProviders`] asks them in that order and falls back to the
+//! next one when a provider fails; a rate limited provider is skipped for
+//! [`RATE_LIMIT_COOLDOWN`] so it is not hammered while it recovers.
+
+use core::fmt;
+use serde_json::Value;
+use std::{
+    error::Error,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    currency::{self, Currency, USD},
+    http::Http,
+    playlist::{eq, find, trim},
+    pyth::{decode_price_update, RECEIVER_PROGRAM_ID, SOL_USD_PRICE_ACCOUNT},
+    rpc::RpcError,
+    state::SolPrice,
+    tokens::TokenPrice,
+    transport::{Method, Transport},
+};
+
+/// Wrapped SOL, the mint Jupiter prices SOL under.
+pub const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+/// Every provider name the setting accepts.
+pub const PROVIDER_NAMES: &[&str] = &["coingecko", "jupiter", "pyth"];
+
+/// The one provider that quotes in currencies other than USD.
+const ANY_QUOTE_PROVIDER: &str = "coingecko";
+
+pub const DEFAULT_PRICE_PROVIDERS: &str = "coingecko, jupiter, pyth";
+
+/// How long a provider that answered with HTTP 429 is left alone.
+pub const RATE_LIMIT_COOLDOWN: Duration = Duration::from_secs(5 * 60);
+
+/// A source of prices quoted in `quote`.
+pub trait PriceProvider<T> {
+    fn name(&self) -> &'static str;
+
+    fn sol_price(&self, http: &mut Http<T>, quote: &Currency) -> Result<SolPrice, Box<dyn Error>>;
+
+    /// Price of each of `mints` the provider knows, the others are left out.
+    fn token_prices(
+        &self,
+        http: &mut Http<T>,
+        mints: &[String],
+        quote: &Currency,
+    ) -> Result<Vec<TokenPrice>, Box<dyn Error>>;
+}
+
+/// For the providers that only quote in USD.
+fn require_usd(provider: &str, quote: &Currency) -> Result<(), Box<dyn Error>> {
+    if quote.is_usd() {
+        Ok(())
+    } else {
+        Err(format!("{} only quotes USD, not {}", provider, quote.label()).into())
+    }
+}
+
+/// CoinGecko's public simple price API.
+pub struct CoinGecko;
+
+impl<T: Transport> PriceProvider<T> for CoinGecko {
+    fn name(&self) -> &'static str {
+        "coingecko"
+    }
+
+    fn sol_price(&self, http: &mut Http<T>, quote: &Currency) -> Result<SolPrice, Box<dyn Error>> {
+        let headers = [("accept", "application/json")];
+        let url = format!(
+            "https://api.coingecko.com/api/v3/simple/price?ids=solana&vs_currencies={}&include_24hr_change=true",
+            quote.code
+        );
+        let response = http.http_request(Method::Get, &url, &headers, None)?;
+        parse_coingecko_sol_price(&response, quote)
+    }
+
+    fn token_prices(
+        &self,
+        http: &mut Http<T>,
+        mints: &[String],
+        quote: &Currency,
+    ) -> Result<Vec<TokenPrice>, Box<dyn Error>> {
+        let headers = [("accept", "application/json")];
+        let url = format!(
+            "https://api.coingecko.com/api/v3/simple/token_price/solana?contract_addresses={}&vs_currencies={}",
+            mints.join(","),
+            quote.code
+        );
+        let response = http.http_request(Method::Get, &url, &headers, None)?;
+        parse_coingecko_token_prices(&response, mints, quote)
+    }
+}
+
+/// CoinGecko simple price response -> SOL price in `quote` and its 24h
+/// change.
+pub fn parse_coingecko_sol_price(
+    response: &Value,
+    quote: &Currency,
+) -> Result<SolPrice, Box<dyn Error>> {
+    let solana = &response["solana"];
+    Ok(SolPrice {
+        price: solana[quote.code]
+            .as_f64()
+            .ok_or("no SOL price found in the response")?,
+        change_24h: solana[format!("{}_24h_change", quote.code)].as_f64(),
+        oracle: None,
+    })
+}
+
+/// CoinGecko token price response -> the price of each of `mints` in
+/// `quote`. The response may key the mints in lowercase, so they are matched
+/// ignoring case.
+pub fn parse_coingecko_token_prices(
+    response: &Value,
+    mints: &[String],
+    quote: &Currency,
+) -> Result<Vec<TokenPrice>, Box<dyn Error>> {
+    let prices = response
+        .as_object()
+        .ok_or("no token prices found in the response")?;
+    Ok(mints
+        .iter()
+        .filter_map(|mint| {
+            let (_, price) = prices
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(mint))?;
+            Some(TokenPrice {
+                mint: mint.clone(),
+                price: price[quote.code].as_f64()?,
+            })
+        })
+        .collect())
+}
+
+/// Jupiter's price API, which prices any mint with on-chain liquidity.
+pub struct Jupiter;
+
+impl Jupiter {
+    fn get<T: Transport>(
+        &self,
+        http: &mut Http<T>,
+        mints: &[String],
+    ) -> Result<Value, Box<dyn Error>> {
+        let headers = [("accept", "application/json")];
+        let url = format!("https://lite-api.jup.ag/price/v3?ids={}", mints.join(","));
+        http.http_request(Method::Get, &url, &headers, None)
+    }
+}
+
+impl<T: Transport> PriceProvider<T> for Jupiter {
+    fn name(&self) -> &'static str {
+        "jupiter"
+    }
+
+    fn sol_price(&self, http: &mut Http<T>, quote: &Currency) -> Result<SolPrice, Box<dyn Error>> {
+        require_usd("jupiter", quote)?;
+        let response = self.get(http, &[SOL_MINT.to_string()])?;
+        parse_jupiter_sol_price(&response)
+    }
+
+    fn token_prices(
+        &self,
+        http: &mut Http<T>,
+        mints: &[String],
+        quote: &Currency,
+    ) -> Result<Vec<TokenPrice>, Box<dyn Error>> {
+        require_usd("jupiter", quote)?;
+        let response = self.get(http, mints)?;
+        parse_jupiter_prices(&response, mints)
+    }
+}
+
+/// Jupiter price response -> SOL/USD and its 24h change.
+pub fn parse_jupiter_sol_price(response: &Value) -> Result<SolPrice, Box<dyn Error>> {
+    let sol = &response[SOL_MINT];
+    Ok(SolPrice {
+        price: sol["usdPrice"]
+            .as_f64()
+            .ok_or("no SOL price found in the response")?,
+        change_24h: sol["priceChange24h"].as_f64(),
+        oracle: None,
+    })
+}
+
+/// Jupiter price response -> the USD price of each of `mints` it lists.
+pub fn parse_jupiter_prices(
+    response: &Value,
+    mints: &[String],
+) -> Result<Vec<TokenPrice>, Box<dyn Error>> {
+    if !response.is_object() {
+        return Err("no token prices found in the response".into());
+    }
+    Ok(mints
+        .iter()
+        .filter_map(|mint| {
+            Some(TokenPrice {
+                mint: mint.clone(),
+                price: response[mint]["usdPrice"].as_f64()?,
+            })
+        })
+        .collect())
+}
+
+/// The Pyth SOL/USD feed, read from the configured RPC endpoint so no other
+/// API has to be trusted. The price comes with Pyth's confidence interval and
+/// publish time.
+pub struct Pyth {
+    pub account: String,
+}
+
+impl Default for Pyth {
+    fn default() -> Self {
+        Self {
+            account: SOL_USD_PRICE_ACCOUNT.to_string(),
+        }
+    }
+}
+
+impl<T: Transport> PriceProvider<T> for Pyth {
+    fn name(&self) -> &'static str {
+        "pyth"
+    }
+
+    fn sol_price(&self, http: &mut Http<T>, quote: &Currency) -> Result<SolPrice, Box<dyn Error>> {
+        require_usd("pyth", quote)?;
+        let account = http.get_account_info(&self.account)?;
+        if account.owner != RECEIVER_PROGRAM_ID {
+            return Err(format!("{} is not owned by the Pyth receiver", self.account).into());
+        }
+        Ok(decode_price_update(&account.decode_data()?)?.into())
+    }
+
+    fn token_prices(
+        &self,
+        _http: &mut Http<T>,
+        _mints: &[String],
+        _quote: &Currency,
+    ) -> Result<Vec<TokenPrice>, Box<dyn Error>> {
+        Err("pyth only provides the SOL price".into())
+    }
+}
+
+/// Build the provider registered under a setting name.
+fn provider_for<T: Transport>(name: &str) -> Option<Box<dyn PriceProvider<T>>> {
+    let provider: Box<dyn PriceProvider<T>> = match name {
+        "coingecko" => Box::new(CoinGecko),
+        "jupiter" => Box::new(Jupiter),
+        "pyth" => Box::new(Pyth::default()),
+        _ => return None,
+    };
+    Some(provider)
+}
+
+/// An HTTP 429 or a JSON-RPC rate limit error.
+fn is_rate_limited(err: &(dyn Error + 'static)) -> bool {
+    match err.downcast_ref::<RpcError>() {
+        Some(err) => err.code == 429,
+        None => err.to_string() == "HTTP Error: Status code 429",
+    }
+}
+
+struct Slot<T> {
+    provider: Box<dyn PriceProvider<T>>,
+    cooldown_until: Option<Instant>,
+}
+
+/// Price providers in order of preference, see the module docs.
+pub struct PriceProviders<T> {
+    providers: Vec<Slot<T>>,
+}
+
+impl<T: Transport> PriceProviders<T> {
+    pub fn new(providers: Vec<Box<dyn PriceProvider<T>>>) -> Self {
+        Self {
+            providers: providers
+                .into_iter()
+                .map(|provider| Slot {
+                    provider,
+                    cooldown_until: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Build the providers named by the `price_providers` setting.
+    pub fn from_setting(setting: &str) -> Result<Self, PriceProvidersError> {
+        let providers = parse(setting)?
+            .into_iter()
+            .map(|name| provider_for(name).ok_or(PriceProvidersError::UnknownProvider))
+            .collect::<Result<_, _>>()?;
+        Ok(Self::new(providers))
+    }
+
+    pub fn sol_price(
+        &mut self,
+        http: &mut Http<T>,
+        quote: &Currency,
+    ) -> Result<SolPrice, Box<dyn Error>> {
+        self.first_success(|provider| provider.sol_price(http, quote))
+    }
+
+    pub fn token_prices(
+        &mut self,
+        http: &mut Http<T>,
+        mints: &[String],
+        quote: &Currency,
+    ) -> Result<Vec<TokenPrice>, Box<dyn Error>> {
+        if mints.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.first_success(|provider| provider.token_prices(http, mints, quote))
+    }
+
+    /// Ask each provider in turn until one succeeds.
+    fn first_success<R>(
+        &mut self,
+        mut ask: impl FnMut(&dyn PriceProvider<T>) -> Result<R, Box<dyn Error>>,
+    ) -> Result<R, Box<dyn Error>> {
+        let now = Instant::now();
+        let mut failures = Vec::new();
+        for slot in &mut self.providers {
+            let name = slot.provider.name();
+            if slot.cooldown_until.is_some_and(|until| until > now) {
+                failures.push(format!("{}: rate limited", name));
+                continue;
+            }
+            match ask(slot.provider.as_ref()) {
+                Ok(result) => return Ok(result),
+                Err(err) => {
+                    println!("Price provider {} failed: {}", name, err);
+                    if is_rate_limited(err.as_ref()) {
+                        slot.cooldown_until = Some(now + RATE_LIMIT_COOLDOWN);
+                    }
+                    failures.push(format!("{}: {}", name, err));
+                }
+            }
+        }
+        if failures.is_empty() {
+            return Err("no price provider configured".into());
+        }
+        Err(failures.join("; ").into())
+    }
+}
+
+impl<T: Transport> Default for PriceProviders<T> {
+    fn default() -> Self {
+        Self::from_setting(DEFAULT_PRICE_PROVIDERS).expect("the default providers are valid")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceProvidersError {
+    Empty,
+    EmptyEntry,
+    UnknownProvider,
+    UnsupportedQuote,
+}
+
+impl PriceProvidersError {
+    pub const fn message(&self) -> &'static str {
+        match self {
+            PriceProvidersError::Empty => "no price provider is configured",
+            PriceProvidersError::EmptyEntry => {
+                "the price providers list has an empty entry (stray comma?)"
+            }
+            PriceProvidersError::UnknownProvider => {
+                "unknown price provider, expected coingecko, jupiter or pyth"
+            }
+            PriceProvidersError::UnsupportedQuote => {
+                "only coingecko quotes currencies other than usd, add it to the price providers"
+            }
+        }
+    }
+}
+
+impl fmt::Display for PriceProvidersError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl Error for PriceProvidersError {}
+
+const fn provider_name(entry: &[u8]) -> Result<&'static str, PriceProvidersError> {
+    let name = trim(entry);
+    if name.is_empty() {
+        return Err(PriceProvidersError::EmptyEntry);
+    }
+    let mut i = 0;
+    while i < PROVIDER_NAMES.len() {
+        if eq(name, PROVIDER_NAMES[i].as_bytes()) {
+            return Ok(PROVIDER_NAMES[i]);
+        }
+        i += 1;
+    }
+    Err(PriceProvidersError::UnknownProvider)
+}
+
+/// Validate the setting without allocating, usable in `const` context.
+pub const fn validate(setting: &str) -> Result<(), PriceProvidersError> {
+    let mut rest = setting.as_bytes();
+    if trim(rest).is_empty() {
+        return Err(PriceProvidersError::Empty);
+    }
+    loop {
+        let (entry, tail) = rest.split_at(find(rest, b','));
+        if let Err(err) = provider_name(entry) {
+            return Err(err);
+        }
+        match tail {
+            [] => return Ok(()),
+            [_, tail @ ..] => rest = tail,
+        }
+    }
+}
+
+/// Panics with a readable message on an invalid setting. Meant for
+/// `const _: () = price::check(CONFIG.price_providers);` so it fails the build.
+pub const fn check(setting: &str) {
+    if let Err(err) = validate(setting) {
+        panic!("{}", err.message());
+    }
+}
+
+/// Validate the providers against the `quote_currency` setting: a currency
+/// other than USD needs a provider that quotes it. An unknown currency is
+/// left to [`currency::check`].
+pub const fn validate_quote(setting: &str, quote: &str) -> Result<(), PriceProvidersError> {
+    if let Err(err) = validate(setting) {
+        return Err(err);
+    }
+    match currency::find(quote) {
+        Ok(quote) if !eq(quote.code.as_bytes(), USD.code.as_bytes()) => {}
+        _ => return Ok(()),
+    }
+    let mut rest = setting.as_bytes();
+    loop {
+        let (entry, tail) = rest.split_at(find(rest, b','));
+        if eq(trim(entry), ANY_QUOTE_PROVIDER.as_bytes()) {
+            return Ok(());
+        }
+        match tail {
+            [] => return Err(PriceProvidersError::UnsupportedQuote),
+            [_, tail @ ..] => rest = tail,
+        }
+    }
+}
+
+/// Panics with a readable message when no provider quotes the currency.
+/// Meant for `const _: () = price::check_quote(CONFIG.price_providers,
+/// CONFIG.quote_currency);` so it fails the build.
+pub const fn check_quote(setting: &str, quote: &str) {
+    if let Err(err) = validate_quote(setting, quote) {
+        panic!("{}", err.message());
+    }
+}
+
+pub fn parse(setting: &str) -> Result<Vec<&'static str>, PriceProvidersError> {
+    validate(setting)?;
+    setting
+        .split(',')
+        .map(|entry| provider_name(entry.as_bytes()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockTransport;
+    use serde_json::json;
+
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+    const JUP: &str = "JUPyiwrYJFskUPiHa7hkeR8VUtAeFoSYbKedZNsDvCN";
+
+    fn http(transport: MockTransport) -> Http<MockTransport> {
+        Http::init(transport, "https://rpc.example.com")
+    }
+
+    #[test]
+    fn each_provider_reads_its_own_api() {
+        let mut http = http(
+            MockTransport::new()
+                .respond(200, include_str!("../fixtures/coingecko_simple_price.json"))
+                .respond(200, include_str!("../fixtures/jupiter_price.json"))
+                .respond(
+                    200,
+                    include_str!("../fixtures/pyth_sol_usd_price_update.json"),
+                ),
+        );
+        let coingecko = CoinGecko.sol_price(&mut http, &USD).unwrap();
+        assert_eq!(
+            (coingecko.price, coingecko.change_24h),
+            (187.42, Some(-3.18))
+        );
+        let jupiter = Jupiter.sol_price(&mut http, &USD).unwrap();
+        assert_eq!((jupiter.price, jupiter.change_24h), (187.31, Some(2.31)));
+        let pyth = Pyth::default().sol_price(&mut http, &USD).unwrap();
+        assert!((pyth.price - 187.42).abs() < 1e-9);
+        assert_eq!(pyth.oracle.unwrap().publish_time, 1_736_258_703);
+
+        let requests = http.transport().requests();
+        assert!(requests[1].uri.ends_with(&format!("ids={}", SOL_MINT)));
+        assert_eq!(requests[2].uri, "https://rpc.example.com");
+    }
+
+    #[test]
+    fn token_prices_are_matched_to_their_mints() {
+        let mints = [USDC.to_string(), JUP.to_string()];
+        let expected = [
+            TokenPrice {
+                mint: USDC.to_string(),
+                price: 0.9998,
+            },
+            TokenPrice {
+                mint: JUP.to_string(),
+                price: 0.7512,
+            },
+        ];
+        let mut http = http(
+            MockTransport::new()
+                .respond(200, include_str!("../fixtures/coingecko_token_price.json")),
+        );
+        assert_eq!(
+            CoinGecko.token_prices(&mut http, &mints, &USD).unwrap(),
+            expected
+        );
+        assert!(http.transport().requests()[0]
+            .uri
+            .contains(&format!("contract_addresses={},{}", USDC, JUP)));
+
+        let response: Value =
+            serde_json::from_str(include_str!("../fixtures/jupiter_price.json")).unwrap();
+        assert_eq!(
+            parse_jupiter_prices(&response, &mints).unwrap(),
+            expected[1..]
+        );
+        assert!(parse_coingecko_sol_price(&json!({ "solana": {} }), &USD).is_err());
+    }
+
+    #[test]
+    fn falls_back_to_the_next_provider() {
+        let mut http = http(
+            MockTransport::new()
+                .respond(429, "Too Many Requests")
+                .respond(200, include_str!("../fixtures/jupiter_price.json"))
+                // CoinGecko is cooling down, Jupiter fails, Pyth answers
+                .fail("connection reset")
+                .respond(
+                    200,
+                    include_str!("../fixtures/pyth_sol_usd_price_update.json"),
+                ),
+        );
+        let mut providers = PriceProviders::default();
+        assert_eq!(providers.sol_price(&mut http, &USD).unwrap().price, 187.31);
+        assert!((providers.sol_price(&mut http, &USD).unwrap().price - 187.42).abs() < 1e-9);
+        assert_eq!(http.transport().requests().len(), 4);
+        assert_eq!(http.transport().remaining(), 0);
+    }
+
+    #[test]
+    fn pyth_only_trusts_the_receiver_program() {
+        let fixture = include_str!("../fixtures/pyth_sol_usd_price_update.json");
+        let mut http =
+            http(MockTransport::new().respond(200, fixture.replace(RECEIVER_PROGRAM_ID, SOL_MINT)));
+        assert_eq!(
+            Pyth::default()
+                .sol_price(&mut http, &USD)
+                .unwrap_err()
+                .to_string(),
+            format!(
+                "{} is not owned by the Pyth receiver",
+                SOL_USD_PRICE_ACCOUNT
+            )
+        );
+    }
+
+    #[test]
+    fn other_currencies_skip_the_usd_only_providers() {
+        let eur = crate::currency::find("eur").unwrap();
+        let mut http = http(MockTransport::new().respond(200, r#"{"solana":{"eur":172.1}}"#));
+        let mut providers = PriceProviders::from_setting("pyth, jupiter, coingecko").unwrap();
+        assert_eq!(providers.sol_price(&mut http, &eur).unwrap(), 172.1.into());
+
+        let requests = http.transport().requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].uri.contains("ids=solana&vs_currencies=eur&"));
+    }
+
+    #[test]
+    fn reports_every_failure() {
+        let mut http = http(
+            MockTransport::new()
+                .respond(500, "")
+                .fail("connection reset"),
+        );
+        let mut providers = PriceProviders::new(vec![
+            Box::new(CoinGecko),
+            Box::new(Jupiter),
+            Box::new(Pyth::default()),
+        ]);
+        assert_eq!(
+            providers
+                .token_prices(&mut http, &[USDC.to_string()], &USD)
+                .unwrap_err()
+                .to_string(),
+            "coingecko: HTTP Error: Status code 500; jupiter: connection reset; \
+             pyth: pyth only provides the SOL price"
+        );
+        // nothing to price, nothing is sent
+        assert_eq!(providers.token_prices(&mut http, &[], &USD).unwrap(), []);
+    }
+
+    #[test]
+    fn validates_the_setting() {
+        assert_eq!(parse(" pyth,coingecko ").unwrap(), ["pyth", "coingecko"]);
+        assert_eq!(validate(""), Err(PriceProvidersError::Empty));
+        assert_eq!(validate("pyth,"), Err(PriceProvidersError::EmptyEntry));
+        assert_eq!(
+            validate("pyth, binance"),
+            Err(PriceProvidersError::UnknownProvider)
+        );
+    }
+
+    #[test]
+    fn other_currencies_need_coingecko() {
+        assert_eq!(validate_quote("pyth", "usd"), Ok(()));
+        assert_eq!(validate_quote("jupiter, coingecko", " EUR "), Ok(()));
+        assert_eq!(
+            validate_quote("jupiter, pyth", "eur"),
+            Err(PriceProvidersError::UnsupportedQuote)
+        );
+        assert_eq!(
+            validate_quote("pyth,", "eur"),
+            Err(PriceProvidersError::EmptyEntry)
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Block production of the watched validator, per epoch.
+//!
+//! `getBlockProduction` only covers the current epoch, so the device keeps the
+//! last numbers it saw of each epoch and saves them to flash whenever an epoch
+//! closes. That builds up a short history even across restarts.
+
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+use crate::rpc::BlockProduction;
+
+/// Storage key of the history.
+pub const PRODUCTION_KEY: &str = "block_prod";
+
+/// Epochs kept, the current one included.
+pub const PRODUCTION_EPOCHS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochProduction {
+    pub epoch: u64,
+    pub leader_slots: u64,
+    pub blocks_produced: u64,
+}
+
+impl EpochProduction {
+    /// Production of `identity` in `epoch`, zero when it had no leader slot.
+    pub fn new(epoch: u64, production: &BlockProduction, identity: &str) -> Self {
+        let (leader_slots, blocks_produced) = production
+            .by_identity
+            .get(identity)
+            .copied()
+            .unwrap_or_default();
+        Self {
+            epoch,
+            leader_slots,
+            blocks_produced,
+        }
+    }
+
+    pub fn skipped(&self) -> u64 {
+        self.leader_slots.saturating_sub(self.blocks_produced)
+    }
+
+    /// Skipped leader slots in percent, `None` without leader slots.
+    pub fn skip_rate(&self) -> Option<f64> {
+        (self.leader_slots > 0).then(|| self.skipped() as f64 * 100.0 / self.leader_slots as f64)
+    }
+}
+
+/// The last [`PRODUCTION_EPOCHS`] epochs, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProductionHistory {
+    pub epochs: Vec<EpochProduction>,
+}
+
+impl ProductionHistory {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).unwrap_or_default()
+    }
+
+    /// The latest epoch.
+    pub fn current(&self) -> Option<&EpochProduction> {
+        self.epochs.last()
+    }
+
+    /// Closed epochs, latest first.
+    pub fn past(&self) -> impl Iterator<Item = &EpochProduction> {
+        self.epochs.iter().rev().skip(1)
+    }
+
+    /// Update the epoch of `production` or start a new one. Returns whether
+    /// that closed the previous epoch, which is when the history is saved.
+    pub fn record(&mut self, production: EpochProduction) -> bool {
+        match self.epochs.last_mut() {
+            Some(last) if last.epoch == production.epoch => {
+                *last = production;
+                false
+            }
+            // a late response for an epoch already closed
+            Some(last) if last.epoch > production.epoch => false,
+            last => {
+                let closed = last.is_some();
+                self.epochs.push(production);
+                let excess = self.epochs.len().saturating_sub(PRODUCTION_EPOCHS);
+                self.epochs.drain(..excess);
+                closed
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::sample_production_history;
+
+    fn production(epoch: u64, leader_slots: u64, blocks_produced: u64) -> EpochProduction {
+        EpochProduction {
+            epoch,
+            leader_slots,
+            blocks_produced,
+        }
+    }
+
+    #[test]
+    fn epochs_close_when_the_next_one_starts() {
+        let mut history = ProductionHistory::default();
+        assert!(!history.record(production(726, 400, 396)));
+        assert!(!history.record(production(726, 1_200, 1_188)));
+        assert_eq!(history.current().unwrap().skip_rate(), Some(1.0));
+
+        for epoch in 727..=730 {
+            assert!(history.record(production(epoch, 0, 0)));
+        }
+        assert!(!history.record(production(729, 1, 1)));
+        let epochs: Vec<u64> = history.epochs.iter().map(|p| p.epoch).collect();
+        assert_eq!(epochs, [727, 728, 729, 730]);
+        assert_eq!(history.current().unwrap().skip_rate(), None);
+    }
+
+    #[test]
+    fn history_round_trips_through_storage() {
+        let history = sample_production_history();
+        let past: Vec<u64> = history.past().map(|p| p.epoch).collect();
+        assert_eq!(past, [729, 728, 727]);
+        assert_eq!(
+            ProductionHistory::from_bytes(&history.to_bytes()).unwrap(),
+            history
+        );
+        assert!(ProductionHistory::from_bytes(b"\xff").is_err());
+    }
+}
@@ -0,0 +1,130 @@
+//! Pyth price feeds read straight from Solana.
+//!
+//! Pyth posts its feeds as `PriceUpdateV2` accounts owned by the Pyth receiver
+//! program. An account holds an Anchor discriminator, the write authority, the
+//! verification level and the price message itself.
+
+use std::error::Error;
+
+use crate::{
+    reader::Reader,
+    state::{OracleQuote, SolPrice},
+};
+
+/// Owner of every `PriceUpdateV2` account.
+pub const RECEIVER_PROGRAM_ID: &str = "rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ";
+
+/// Sponsored SOL/USD price feed account (shard 0) of the Pyth push oracle.
+pub const SOL_USD_PRICE_ACCOUNT: &str = "7UVimffxr9ow1uXYxsr4LHAcV58mLzhmwaYKvRRJjLPq";
+
+/// First 8 bytes of `sha256("account:PriceUpdateV2")`.
+const PRICE_UPDATE_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriceUpdate {
+    pub feed_id: [u8; 32],
+    /// Price in units of `10^exponent`.
+    pub price: i64,
+    /// Confidence interval around the price, in units of `10^exponent`.
+    pub confidence: u64,
+    pub exponent: i32,
+    /// Unix time the price was published by Pyth.
+    pub publish_time: i64,
+}
+
+impl PriceUpdate {
+    pub fn value(&self) -> f64 {
+        self.scaled(self.price as f64)
+    }
+
+    pub fn confidence_value(&self) -> f64 {
+        self.scaled(self.confidence as f64)
+    }
+
+    fn scaled(&self, units: f64) -> f64 {
+        units * 10f64.powi(self.exponent)
+    }
+}
+
+impl From<PriceUpdate> for SolPrice {
+    fn from(update: PriceUpdate) -> Self {
+        Self {
+            price: update.value(),
+            change_24h: None,
+            oracle: Some(OracleQuote {
+                confidence: update.confidence_value(),
+                publish_time: update.publish_time,
+            }),
+        }
+    }
+}
+
+/// Decode the data of a `PriceUpdateV2` account.
+pub fn decode_price_update(data: &[u8]) -> Result<PriceUpdate, Box<dyn Error>> {
+    let mut reader = Reader::new(data, "price account");
+    if reader.take::<8>()? != PRICE_UPDATE_DISCRIMINATOR {
+        return Err("not a Pyth price update account".into());
+    }
+    let _write_authority = reader.take::<32>()?;
+    // Partial { num_signatures: u8 } or Full
+    match reader.u8()? {
+        0 => {
+            reader.u8()?;
+        }
+        1 => {}
+        level => return Err(format!("unknown Pyth verification level {}", level).into()),
+    }
+    let feed_id = reader.take::<32>()?;
+    let price = reader.i64()?;
+    let confidence = reader.u64()?;
+    let exponent = reader.i32()?;
+    let publish_time = reader.i64()?;
+    Ok(PriceUpdate {
+        feed_id,
+        price,
+        confidence,
+        exponent,
+        publish_time,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::{AccountInfo, RpcResponse, WithContext};
+
+    fn fixture_data() -> Vec<u8> {
+        let response: RpcResponse<WithContext<Option<AccountInfo>>> =
+            serde_json::from_str(include_str!("../fixtures/pyth_sol_usd_price_update.json"))
+                .unwrap();
+        let account = response.result.unwrap().value.unwrap();
+        account.decode_data().unwrap()
+    }
+
+    #[test]
+    fn decodes_the_sol_usd_feed() {
+        let update = decode_price_update(&fixture_data()).unwrap();
+        assert_eq!(update.price, 18_742_000_000);
+        assert_eq!(update.confidence, 9_500_000);
+        assert_eq!(update.exponent, -8);
+        assert_eq!(update.publish_time, 1_736_258_703);
+        assert_eq!(update.feed_id[..2], [0xef, 0x0d]);
+
+        let price = SolPrice::from(update);
+        assert!((price.price - 187.42).abs() < 1e-9);
+        let oracle = price.oracle.unwrap();
+        assert!((oracle.confidence - 0.095).abs() < 1e-9);
+        assert_eq!(oracle.publish_time, 1_736_258_703);
+    }
+
+    #[test]
+    fn rejects_other_accounts() {
+        let mut data = fixture_data();
+        assert!(decode_price_update(&data[..60]).is_err());
+        data[0] ^= 0xff;
+        assert_eq!(
+            decode_price_update(&data).unwrap_err().to_string(),
+            "not a Pyth price update account"
+        );
+    }
+}
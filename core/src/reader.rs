@@ -0,0 +1,59 @@
+//! Little-endian fields of on-chain account data.
+
+use std::error::Error;
+
+/// Reads little-endian fields one after the other.
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    /// What the data is, for the error when it runs out: `price account`.
+    account: &'static str,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8], account: &'static str) -> Self {
+        Self {
+            data,
+            offset: 0,
+            account,
+        }
+    }
+
+    pub(crate) fn take<const N: usize>(&mut self) -> Result<[u8; N], Box<dyn Error>> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + N)
+            .ok_or_else(|| format!("the {} is too short", self.account))?;
+        self.offset += N;
+        Ok(bytes.try_into().expect("N bytes"))
+    }
+
+    /// Step over `len` bytes the caller has no use for.
+    pub(crate) fn skip(&mut self, len: usize) -> Result<(), Box<dyn Error>> {
+        if self.data.len() < self.offset + len {
+            return Err(format!("the {} is too short", self.account).into());
+        }
+        self.offset += len;
+        Ok(())
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, Box<dyn Error>> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, Box<dyn Error>> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    pub(crate) fn i32(&mut self) -> Result<i32, Box<dyn Error>> {
+        Ok(i32::from_le_bytes(self.take()?))
+    }
+
+    pub(crate) fn i64(&mut self) -> Result<i64, Box<dyn Error>> {
+        Ok(i64::from_le_bytes(self.take()?))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, Box<dyn Error>> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+}
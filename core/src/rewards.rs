@@ -0,0 +1,160 @@
+//! Staking rewards of the watched wallets' stake accounts, per epoch.
+//!
+//! Rewards are credited once at the start of each epoch for the one before,
+//! so `getInflationReward` is only asked once per epoch and the totals are
+//! saved to flash right away. The APY is estimated from the reward rate of
+//! the epochs kept.
+
+use serde::{Deserialize, Serialize};
+use std::{error::Error, time::Duration};
+
+use crate::rpc::{EpochInfo, InflationReward};
+
+/// Storage key of the history.
+pub const REWARDS_KEY: &str = "stake_rewards";
+
+/// Epochs kept, one bar each on the rewards page.
+pub const REWARDS_EPOCHS: usize = 10;
+
+/// Slots and slot time of an epoch when the cluster's are not known yet.
+const DEFAULT_SLOTS_IN_EPOCH: u64 = 432_000;
+const DEFAULT_SLOT_TIME: Duration = Duration::from_millis(400);
+
+const SECONDS_PER_YEAR: f64 = 365.25 * 24.0 * 60.0 * 60.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochReward {
+    pub epoch: u64,
+    /// Lamports credited over every stake account.
+    pub amount: u64,
+    /// Lamports of the rewarded accounts before the reward.
+    pub staked: u64,
+}
+
+impl EpochReward {
+    /// Sum of the `rewards` of each stake account for `epoch`.
+    pub fn new(epoch: u64, rewards: &[Option<InflationReward>]) -> Self {
+        let rewards = rewards.iter().flatten();
+        Self {
+            epoch,
+            amount: rewards.clone().map(|r| r.amount).sum(),
+            staked: rewards
+                .map(|r| r.post_balance.saturating_sub(r.amount))
+                .sum(),
+        }
+    }
+
+    /// Reward over the stake, `None` when nothing was rewarded.
+    pub fn rate(&self) -> Option<f64> {
+        (self.staked > 0).then(|| self.amount as f64 / self.staked as f64)
+    }
+}
+
+/// The last [`REWARDS_EPOCHS`] epochs, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RewardHistory {
+    pub epochs: Vec<EpochReward>,
+}
+
+impl RewardHistory {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).unwrap_or_default()
+    }
+
+    pub fn latest(&self) -> Option<&EpochReward> {
+        self.epochs.last()
+    }
+
+    /// Whether the rewards of `epoch` are known, or of a later one.
+    pub fn covers(&self, epoch: u64) -> bool {
+        self.latest().is_some_and(|latest| latest.epoch >= epoch)
+    }
+
+    /// Add the rewards of a new epoch. Returns whether they were added, which
+    /// is when the history is saved.
+    pub fn record(&mut self, reward: EpochReward) -> bool {
+        if self.covers(reward.epoch) {
+            return false;
+        }
+        self.epochs.push(reward);
+        let excess = self.epochs.len().saturating_sub(REWARDS_EPOCHS);
+        self.epochs.drain(..excess);
+        true
+    }
+
+    /// Yearly yield in percent if the average rate of the kept epochs goes on
+    /// compounding every `epoch_length`. `None` before any reward.
+    pub fn apy(&self, epoch_length: Duration) -> Option<f64> {
+        let rates: Vec<f64> = self.epochs.iter().filter_map(EpochReward::rate).collect();
+        if rates.is_empty() || epoch_length.is_zero() {
+            return None;
+        }
+        let rate = rates.iter().sum::<f64>() / rates.len() as f64;
+        let epochs_per_year = SECONDS_PER_YEAR / epoch_length.as_secs_f64();
+        Some(((1.0 + rate).powf(epochs_per_year) - 1.0) * 100.0)
+    }
+}
+
+/// How long an epoch takes at the recent `slot_time`, assuming mainnet
+/// numbers for what is not known.
+pub fn epoch_length(epoch: Option<&EpochInfo>, slot_time: Option<Duration>) -> Duration {
+    let slots = epoch.map_or(DEFAULT_SLOTS_IN_EPOCH, |epoch| epoch.slots_in_epoch);
+    slot_time.unwrap_or(DEFAULT_SLOT_TIME) * slots.min(u32::MAX as u64) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rpc::RpcResponse, simulator::sample_reward_history};
+
+    #[test]
+    fn rewards_add_up_over_the_stake_accounts() {
+        let response: RpcResponse<Vec<Option<InflationReward>>> =
+            serde_json::from_str(include_str!("../fixtures/get_inflation_reward.json")).unwrap();
+        let reward = EpochReward::new(729, &response.result.unwrap());
+        assert_eq!(reward, *sample_reward_history().latest().unwrap());
+        assert_eq!(EpochReward::new(729, &[None, None]).rate(), None);
+
+        // a reward above the balance is inconsistent, not a huge stake
+        let inconsistent = InflationReward {
+            epoch: 729,
+            effective_slot: 315_360_000,
+            amount: 10,
+            post_balance: 5,
+            commission: None,
+        };
+        assert_eq!(EpochReward::new(729, &[Some(inconsistent)]).rate(), None);
+    }
+
+    #[test]
+    fn each_epoch_is_recorded_once() {
+        let mut history = RewardHistory::default();
+        for epoch in 718..730 {
+            assert!(history.record(EpochReward::new(epoch, &[])));
+        }
+        assert!(!history.record(EpochReward::new(729, &[])));
+        assert!(!history.record(EpochReward::new(725, &[])));
+        assert_eq!(history.epochs.len(), REWARDS_EPOCHS);
+        assert_eq!(history.epochs[0].epoch, 720);
+        assert!(history.covers(729));
+        assert!(!history.covers(730));
+        assert_eq!(history.apy(Duration::from_secs(172_800)), None);
+    }
+
+    #[test]
+    fn apy_compounds_the_average_rate() {
+        let history = sample_reward_history();
+        let two_days = epoch_length(None, None);
+        assert_eq!(two_days, Duration::from_secs(172_800));
+        let apy = history.apy(two_days).unwrap();
+        assert!((apy - 6.94).abs() < 0.005, "{}", apy);
+        assert_eq!(
+            RewardHistory::from_bytes(&history.to_bytes()).unwrap(),
+            history
+        );
+    }
+}
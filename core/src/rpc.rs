@@ -0,0 +1,832 @@
+//! Typed Solana JSON-RPC client on top of [`Http`].
+//!
+//! Every request gets its own id and the response must echo it back. A
+//! JSON-RPC `error` object is surfaced as an [`RpcError`], even when it comes
+//! with a non-2xx status, so a rate limit is never mistaken for an empty result.
+//!
+//! Calls made in the same refresh can go out as one [`RpcBatch`], paying for a
+//! single HTTPS round-trip, and are matched back to their callers by id.
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use core::{fmt, marker::PhantomData};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, error::Error, time::Duration};
+
+use crate::{
+    http::Http,
+    transport::{Method, Response, Transport},
+};
+
+#[derive(Debug, Serialize)]
+pub struct RpcRequest<'a, P> {
+    pub jsonrpc: &'static str,
+    pub id: u64,
+    pub method: &'a str,
+    pub params: P,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(bound(deserialize = "R: Deserialize<'de>"))]
+pub struct RpcResponse<R> {
+    pub id: Option<u64>,
+    /// `None` only when the field is missing, a `"result": null` is decoded
+    /// as `R` so an `Option` result can be `None`.
+    #[serde(default, deserialize_with = "present")]
+    pub result: Option<R>,
+    pub error: Option<RpcError>,
+}
+
+/// A field that is there, even as `null`.
+fn present<'de, D, R>(deserializer: D) -> Result<Option<R>, D::Error>
+where
+    D: Deserializer<'de>,
+    R: Deserialize<'de>,
+{
+    R::deserialize(deserializer).map(Some)
+}
+
+/// The JSON-RPC `error` object.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default)]
+    pub data: Option<Value>,
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RPC error {}: {}", self.code, self.message)
+    }
+}
+
+impl Error for RpcError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct RpcContext {
+    pub slot: u64,
+}
+
+/// Results that come wrapped in `{ "context": .., "value": .. }`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct WithContext<T> {
+    pub context: RpcContext,
+    pub value: T,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerformanceSample {
+    pub slot: u64,
+    pub num_slots: u64,
+    pub num_transactions: u64,
+    #[serde(default)]
+    pub num_non_vote_transactions: Option<u64>,
+    pub sample_period_secs: u16,
+}
+
+/// Params of a method that takes none, sent as `[]` rather than `null`.
+pub const NO_PARAMS: [(); 0] = [];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpochInfo {
+    pub absolute_slot: u64,
+    pub block_height: u64,
+    pub epoch: u64,
+    pub slot_index: u64,
+    pub slots_in_epoch: u64,
+    #[serde(default)]
+    pub transaction_count: Option<u64>,
+}
+
+impl EpochInfo {
+    pub fn remaining_slots(&self) -> u64 {
+        self.slots_in_epoch.saturating_sub(self.slot_index)
+    }
+
+    /// Share of the epoch's slots already behind, from 0 to 1.
+    pub fn progress(&self) -> f64 {
+        if self.slots_in_epoch == 0 {
+            return 0.0;
+        }
+        self.slot_index.min(self.slots_in_epoch) as f64 / self.slots_in_epoch as f64
+    }
+
+    /// Time until the epoch ends if the remaining slots take `slot_time` each.
+    pub fn time_remaining(&self, slot_time: Duration) -> Duration {
+        slot_time * self.remaining_slots().min(u32::MAX as u64) as u32
+    }
+}
+
+/// An SPL token account from `getTokenAccountsByOwner` with the `jsonParsed`
+/// encoding, reduced to the fields the token page needs.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TokenAccount {
+    pub pubkey: String,
+    pub account: ParsedTokenAccount,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ParsedTokenAccount {
+    pub data: ParsedTokenData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ParsedTokenData {
+    /// `spl-token` or `spl-token-2022`.
+    pub program: String,
+    pub parsed: ParsedTokenInfo,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ParsedTokenInfo {
+    pub info: TokenAccountInfo,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenAccountInfo {
+    pub mint: String,
+    pub owner: String,
+    pub token_amount: TokenAmount,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TokenAmount {
+    /// Raw amount in base units, a string since it may exceed 2^53.
+    pub amount: String,
+    pub decimals: u8,
+}
+
+/// An account from `getAccountInfo` requested with the `base64` encoding.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AccountInfo {
+    pub lamports: u64,
+    pub owner: String,
+    /// `[data, encoding]`
+    pub data: (String, String),
+}
+
+impl AccountInfo {
+    /// The raw account data.
+    pub fn decode_data(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let (data, encoding) = &self.data;
+        if encoding != "base64" {
+            return Err(format!("unexpected account data encoding {}", encoding).into());
+        }
+        Ok(BASE64_STANDARD.decode(data)?)
+    }
+}
+
+/// An account from `getProgramAccounts` with its address.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ProgramAccount {
+    pub pubkey: String,
+    pub account: AccountInfo,
+}
+
+/// A vote account from `getVoteAccounts`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoteAccount {
+    pub vote_pubkey: String,
+    /// Identity of the validator voting with this account.
+    pub node_pubkey: String,
+    /// Stake delegated to the account in the current epoch, in lamports.
+    pub activated_stake: u64,
+    pub commission: u8,
+    pub last_vote: u64,
+    pub root_slot: u64,
+}
+
+/// `getVoteAccounts` splits the accounts by whether they are delinquent.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct VoteAccounts {
+    pub current: Vec<VoteAccount>,
+    pub delinquent: Vec<VoteAccount>,
+}
+
+/// `getBlockProduction`: leader slots and produced blocks of each identity.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockProduction {
+    /// `identity -> (leader slots, blocks produced)`
+    pub by_identity: HashMap<String, (u64, u64)>,
+    pub range: SlotRange,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlotRange {
+    pub first_slot: u64,
+    pub last_slot: u64,
+}
+
+/// Reward credited to a stake account for an epoch, from `getInflationReward`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InflationReward {
+    pub epoch: u64,
+    pub effective_slot: u64,
+    /// Lamports credited.
+    pub amount: u64,
+    /// Balance of the account after the reward.
+    pub post_balance: u64,
+    /// Commission of the vote account when the reward was credited.
+    pub commission: Option<u8>,
+}
+
+/// `[{"votePubkey": .., "keepUnstakedDelinquents": true}]`
+fn vote_accounts_params(vote_pubkey: &str) -> impl Serialize + '_ {
+    [serde_json::json!({
+        "votePubkey": vote_pubkey,
+        "keepUnstakedDelinquents": true,
+    })]
+}
+
+/// `getLeaderSchedule`: the leader slots of each identity, counted from the
+/// first slot of the epoch. `None` when the epoch has no schedule yet.
+pub type LeaderSlots = Option<HashMap<String, Vec<u64>>>;
+
+/// `[null, {"identity": ..}]`, the current epoch.
+fn leader_schedule_params(identity: &str) -> impl Serialize + '_ {
+    ((), serde_json::json!({ "identity": identity }))
+}
+
+/// `[{"identity": ..}]`, the current epoch so far.
+fn block_production_params(identity: &str) -> impl Serialize + '_ {
+    [serde_json::json!({ "identity": identity })]
+}
+
+/// `[[address, ..], {"epoch": ..}]`
+fn inflation_reward_params(addresses: &[String], epoch: u64) -> impl Serialize + '_ {
+    (addresses, serde_json::json!({ "epoch": epoch }))
+}
+
+/// `[pubkey, {"encoding": "base64"}]`
+fn account_info_params(pubkey: &str) -> impl Serialize + '_ {
+    (pubkey, serde_json::json!({ "encoding": "base64" }))
+}
+
+/// `[program_id, {"encoding": "base64", "filters": [..]}]`: accounts of
+/// `data_size` bytes holding the base58 `bytes` at `offset`.
+fn program_accounts_params<'a>(
+    program_id: &'a str,
+    data_size: usize,
+    offset: usize,
+    bytes: &'a str,
+) -> impl Serialize + 'a {
+    (
+        program_id,
+        serde_json::json!({
+            "encoding": "base64",
+            "filters": [
+                { "dataSize": data_size },
+                { "memcmp": { "offset": offset, "bytes": bytes } },
+            ],
+        }),
+    )
+}
+
+/// `[owner, {"programId": ..}, {"encoding": "jsonParsed"}]`
+fn token_accounts_params<'a>(owner: &'a str, program_id: &'a str) -> impl Serialize + 'a {
+    (
+        owner,
+        serde_json::json!({ "programId": program_id }),
+        serde_json::json!({ "encoding": "jsonParsed" }),
+    )
+}
+
+/// Calls collected to be sent as one JSON-RPC batch with [`Http::send_batch`].
+#[derive(Debug, Default)]
+pub struct RpcBatch {
+    calls: Vec<(String, Value)>,
+}
+
+/// Where the result of one call of a batch will be, typed by its result.
+#[derive(Debug)]
+pub struct BatchHandle<R> {
+    index: usize,
+    _result: PhantomData<fn() -> R>,
+}
+
+impl RpcBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add<P, R>(&mut self, method: &str, params: P) -> Result<BatchHandle<R>, Box<dyn Error>>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        self.calls
+            .push((method.to_string(), serde_json::to_value(params)?));
+        Ok(BatchHandle {
+            index: self.calls.len() - 1,
+            _result: PhantomData,
+        })
+    }
+
+    pub fn get_balance(
+        &mut self,
+        wallet: &str,
+    ) -> Result<BatchHandle<WithContext<u64>>, Box<dyn Error>> {
+        self.add("getBalance", (wallet,))
+    }
+
+    pub fn get_recent_performance_samples(
+        &mut self,
+        limit: usize,
+    ) -> Result<BatchHandle<Vec<PerformanceSample>>, Box<dyn Error>> {
+        self.add("getRecentPerformanceSamples", (limit,))
+    }
+
+    pub fn get_epoch_info(&mut self) -> Result<BatchHandle<EpochInfo>, Box<dyn Error>> {
+        self.add("getEpochInfo", NO_PARAMS)
+    }
+
+    pub fn get_slot(&mut self) -> Result<BatchHandle<u64>, Box<dyn Error>> {
+        self.add("getSlot", NO_PARAMS)
+    }
+
+    /// Only the vote account `vote_pubkey`, delinquent or not.
+    pub fn get_vote_accounts(
+        &mut self,
+        vote_pubkey: &str,
+    ) -> Result<BatchHandle<VoteAccounts>, Box<dyn Error>> {
+        self.add("getVoteAccounts", vote_accounts_params(vote_pubkey))
+    }
+
+    /// Block production of `identity` in the current epoch.
+    pub fn get_block_production(
+        &mut self,
+        identity: &str,
+    ) -> Result<BatchHandle<WithContext<BlockProduction>>, Box<dyn Error>> {
+        self.add("getBlockProduction", block_production_params(identity))
+    }
+
+    /// Rewards of `addresses` for `epoch`, `None` for an address that earned
+    /// none.
+    pub fn get_inflation_reward(
+        &mut self,
+        addresses: &[String],
+        epoch: u64,
+    ) -> Result<BatchHandle<Vec<Option<InflationReward>>>, Box<dyn Error>> {
+        self.add(
+            "getInflationReward",
+            inflation_reward_params(addresses, epoch),
+        )
+    }
+
+    /// Accounts of `program_id` that are `data_size` bytes long and hold the
+    /// public key `bytes` at `offset`.
+    pub fn get_program_accounts(
+        &mut self,
+        program_id: &str,
+        data_size: usize,
+        offset: usize,
+        bytes: &str,
+    ) -> Result<BatchHandle<Vec<ProgramAccount>>, Box<dyn Error>> {
+        self.add(
+            "getProgramAccounts",
+            program_accounts_params(program_id, data_size, offset, bytes),
+        )
+    }
+
+    /// Leader slots of `identity` in the current epoch.
+    pub fn get_leader_schedule(
+        &mut self,
+        identity: &str,
+    ) -> Result<BatchHandle<LeaderSlots>, Box<dyn Error>> {
+        self.add("getLeaderSchedule", leader_schedule_params(identity))
+    }
+
+    /// Token accounts of `owner` under one token program.
+    pub fn get_token_accounts_by_owner(
+        &mut self,
+        owner: &str,
+        program_id: &str,
+    ) -> Result<BatchHandle<WithContext<Vec<TokenAccount>>>, Box<dyn Error>> {
+        self.add(
+            "getTokenAccountsByOwner",
+            token_accounts_params(owner, program_id),
+        )
+    }
+
+    /// `None` in the result when the account does not exist.
+    pub fn get_account_info(
+        &mut self,
+        pubkey: &str,
+    ) -> Result<BatchHandle<WithContext<Option<AccountInfo>>>, Box<dyn Error>> {
+        self.add("getAccountInfo", account_info_params(pubkey))
+    }
+
+    pub fn len(&self) -> usize {
+        self.calls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+}
+
+/// Responses of a batch, in the order the calls were added.
+#[derive(Debug)]
+pub struct BatchResponses {
+    methods: Vec<String>,
+    responses: Vec<Option<RpcResponse<Value>>>,
+}
+
+impl BatchResponses {
+    pub fn get<R: DeserializeOwned>(&self, handle: &BatchHandle<R>) -> Result<R, Box<dyn Error>> {
+        let method = &self.methods[handle.index];
+        let response = self.responses[handle.index]
+            .as_ref()
+            .ok_or_else(|| format!("no response for {} in the batch", method))?;
+        if let Some(error) = &response.error {
+            return Err(error.clone().into());
+        }
+        let result = response
+            .result
+            .clone()
+            .ok_or_else(|| format!("no result in the {} response", method))?;
+        Ok(serde_json::from_value(result)?)
+    }
+}
+
+impl<T: Transport> Http<T> {
+    /// POST a JSON-RPC payload to the configured endpoint.
+    fn post_rpc(&mut self, payload: &str) -> Result<Response, Box<dyn Error>> {
+        let headers = [
+            ("Content-Type", "application/json"),
+            ("Content-Length", &payload.len().to_string()),
+        ];
+        let endpoint = self.sol_endpoint.clone();
+        self.transport
+            .request(Method::Post, &endpoint, &headers, Some(payload.as_bytes()))
+    }
+
+    /// Send `method` with `params` (serialized as the JSON-RPC params array,
+    /// e.g. a tuple) and decode the `result`.
+    pub fn rpc_call<P, R>(&mut self, method: &str, params: P) -> Result<R, Box<dyn Error>>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let id = self.next_rpc_id();
+        let payload = serde_json::to_string(&RpcRequest {
+            jsonrpc: "2.0",
+            id,
+            method,
+            params,
+        })?;
+
+        let response = self.post_rpc(&payload)?;
+
+        let parsed = serde_json::from_slice::<RpcResponse<R>>(&response.body);
+        if let Ok(RpcResponse {
+            error: Some(error), ..
+        }) = parsed
+        {
+            return Err(error.into());
+        }
+        if !(200..=299).contains(&response.status) {
+            return Err(format!("HTTP Error: Status code {}", response.status).into());
+        }
+
+        let parsed = parsed?;
+        if parsed.id != Some(id) {
+            return Err(format!(
+                "response id {:?} does not match request id {}",
+                parsed.id, id
+            )
+            .into());
+        }
+        Ok(parsed
+            .result
+            .ok_or_else(|| format!("no result in the {} response", method))?)
+    }
+
+    /// Send every call of `batch` in a single request. Failures of single
+    /// calls are reported by [`BatchResponses::get`], a failure of the whole
+    /// request (transport, HTTP status, top-level error object) here.
+    pub fn send_batch(&mut self, batch: RpcBatch) -> Result<BatchResponses, Box<dyn Error>> {
+        let ids: Vec<u64> = batch.calls.iter().map(|_| self.next_rpc_id()).collect();
+        let requests: Vec<_> = batch
+            .calls
+            .iter()
+            .zip(&ids)
+            .map(|((method, params), &id)| RpcRequest {
+                jsonrpc: "2.0",
+                id,
+                method,
+                params,
+            })
+            .collect();
+        let payload = serde_json::to_string(&requests)?;
+        let response = self.post_rpc(&payload)?;
+
+        // a batch the endpoint refused as a whole comes back as a single error object
+        if let Ok(RpcResponse {
+            error: Some(error), ..
+        }) = serde_json::from_slice::<RpcResponse<Value>>(&response.body)
+        {
+            return Err(error.into());
+        }
+        if !(200..=299).contains(&response.status) {
+            return Err(format!("HTTP Error: Status code {}", response.status).into());
+        }
+
+        let mut received: Vec<RpcResponse<Value>> = serde_json::from_slice(&response.body)?;
+        let responses = ids
+            .iter()
+            .map(|&id| {
+                let position = received.iter().position(|r| r.id == Some(id))?;
+                Some(received.swap_remove(position))
+            })
+            .collect();
+        Ok(BatchResponses {
+            methods: batch.calls.into_iter().map(|(method, _)| method).collect(),
+            responses,
+        })
+    }
+
+    /// Lamports held by `wallet`.
+    pub fn get_balance(&mut self, wallet: &str) -> Result<u64, Box<dyn Error>> {
+        let balance: WithContext<u64> = self.rpc_call("getBalance", (wallet,))?;
+        Ok(balance.value)
+    }
+
+    /// Latest performance samples, newest first.
+    pub fn get_recent_performance_samples(
+        &mut self,
+        limit: usize,
+    ) -> Result<Vec<PerformanceSample>, Box<dyn Error>> {
+        self.rpc_call("getRecentPerformanceSamples", (limit,))
+    }
+
+    pub fn get_epoch_info(&mut self) -> Result<EpochInfo, Box<dyn Error>> {
+        self.rpc_call("getEpochInfo", NO_PARAMS)
+    }
+
+    pub fn get_slot(&mut self) -> Result<u64, Box<dyn Error>> {
+        self.rpc_call("getSlot", NO_PARAMS)
+    }
+
+    pub fn get_vote_accounts(&mut self, vote_pubkey: &str) -> Result<VoteAccounts, Box<dyn Error>> {
+        self.rpc_call("getVoteAccounts", vote_accounts_params(vote_pubkey))
+    }
+
+    pub fn get_leader_schedule(&mut self, identity: &str) -> Result<LeaderSlots, Box<dyn Error>> {
+        self.rpc_call("getLeaderSchedule", leader_schedule_params(identity))
+    }
+
+    pub fn get_block_production(
+        &mut self,
+        identity: &str,
+    ) -> Result<BlockProduction, Box<dyn Error>> {
+        let production: WithContext<BlockProduction> =
+            self.rpc_call("getBlockProduction", block_production_params(identity))?;
+        Ok(production.value)
+    }
+
+    /// Data of the account at `pubkey`, an error when it does not exist.
+    pub fn get_account_info(&mut self, pubkey: &str) -> Result<AccountInfo, Box<dyn Error>> {
+        let account: WithContext<Option<AccountInfo>> =
+            self.rpc_call("getAccountInfo", account_info_params(pubkey))?;
+        Ok(account
+            .value
+            .ok_or_else(|| format!("account {} not found", pubkey))?)
+    }
+
+    /// Token accounts of `owner` under one token program.
+    pub fn get_token_accounts_by_owner(
+        &mut self,
+        owner: &str,
+        program_id: &str,
+    ) -> Result<Vec<TokenAccount>, Box<dyn Error>> {
+        let accounts: WithContext<Vec<TokenAccount>> = self.rpc_call(
+            "getTokenAccountsByOwner",
+            token_accounts_params(owner, program_id),
+        )?;
+        Ok(accounts.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockTransport;
+
+    const RPC: &str = "https://rpc.example.com";
+
+    fn http(transport: MockTransport) -> Http<MockTransport> {
+        Http::init(transport, RPC)
+    }
+
+    fn sent(http: &Http<MockTransport>, index: usize) -> Value {
+        let request = &http.transport().requests()[index];
+        serde_json::from_slice(request.body.as_deref().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn requests_carry_increasing_ids() {
+        let mut http = http(
+            MockTransport::new()
+                .respond(200, include_str!("../fixtures/get_balance.json"))
+                .respond(
+                    200,
+                    r#"{"jsonrpc":"2.0","result":{"context":{"slot":1},"value":5},"id":2}"#,
+                ),
+        );
+        assert_eq!(http.get_balance("wallet").unwrap(), 12_345_678_901);
+        assert_eq!(http.get_balance("wallet").unwrap(), 5);
+
+        let first = sent(&http, 0);
+        assert_eq!(first["jsonrpc"], "2.0");
+        assert_eq!(first["id"], 1);
+        assert_eq!(first["method"], "getBalance");
+        assert_eq!(first["params"], serde_json::json!(["wallet"]));
+        assert_eq!(sent(&http, 1)["id"], 2);
+    }
+
+    #[test]
+    fn performance_samples_are_typed() {
+        let mut http = http(MockTransport::new().respond(
+            200,
+            include_str!("../fixtures/get_recent_performance_samples.json"),
+        ));
+        let samples = http.get_recent_performance_samples(1).unwrap();
+        assert_eq!(
+            samples,
+            [PerformanceSample {
+                slot: 315_482_100,
+                num_slots: 150,
+                num_transactions: 245_412,
+                num_non_vote_transactions: Some(62_318),
+                sample_period_secs: 60,
+            }]
+        );
+        assert_eq!(sent(&http, 0)["params"], serde_json::json!([1]));
+    }
+
+    #[test]
+    fn error_objects_become_rpc_errors() {
+        let mut http = http(
+            MockTransport::new()
+                .respond(429, include_str!("../fixtures/rpc_rate_limited.json"))
+                .respond(
+                    200,
+                    r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid param: WrongSize","data":{"slot":7}},"id":2}"#,
+                ),
+        );
+
+        let err = http.get_balance("wallet").unwrap_err();
+        let err = err.downcast_ref::<RpcError>().unwrap();
+        assert_eq!(err.code, 429);
+        assert!(err.message.starts_with("Too many requests"));
+
+        let err = http.get_balance("wallet").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "RPC error -32602: Invalid param: WrongSize"
+        );
+        let err = err.downcast_ref::<RpcError>().unwrap();
+        assert_eq!(err.data, Some(serde_json::json!({ "slot": 7 })));
+    }
+
+    #[test]
+    fn mismatched_ids_and_missing_results_are_rejected() {
+        let mut http = http(
+            MockTransport::new()
+                .respond(
+                    200,
+                    r#"{"jsonrpc":"2.0","result":{"context":{"slot":1},"value":5},"id":9}"#,
+                )
+                .respond(200, r#"{"jsonrpc":"2.0","id":2}"#)
+                .respond(502, "<html>Bad Gateway</html>"),
+        );
+        assert_eq!(
+            http.get_balance("wallet").unwrap_err().to_string(),
+            "response id Some(9) does not match request id 1"
+        );
+        assert_eq!(
+            http.get_balance("wallet").unwrap_err().to_string(),
+            "no result in the getBalance response"
+        );
+        assert_eq!(
+            http.get_balance("wallet").unwrap_err().to_string(),
+            "HTTP Error: Status code 502"
+        );
+    }
+
+    #[test]
+    fn null_results_are_not_missing() {
+        let fixture = include_str!("../fixtures/get_leader_schedule_null.json");
+        let mut single = http(MockTransport::new().respond(200, fixture));
+        assert_eq!(single.get_leader_schedule("node").unwrap(), None);
+
+        let mut batched = http(MockTransport::new().respond(200, format!("[{}]", fixture)));
+        let mut batch = RpcBatch::new();
+        let schedule = batch.get_leader_schedule("node").unwrap();
+        let responses = batched.send_batch(batch).unwrap();
+        assert_eq!(responses.get(&schedule).unwrap(), None);
+    }
+
+    #[test]
+    fn batch_responses_are_matched_by_id() {
+        let mut http =
+            http(MockTransport::new().respond(200, include_str!("../fixtures/batch_refresh.json")));
+        let mut batch = RpcBatch::new();
+        let balance = batch.get_balance("wallet").unwrap();
+        let samples = batch.get_recent_performance_samples(1).unwrap();
+        let epoch = batch.get_epoch_info().unwrap();
+        assert_eq!(batch.len(), 3);
+        let responses = http.send_batch(batch).unwrap();
+
+        assert_eq!(responses.get(&balance).unwrap().value, 12_345_678_901);
+        assert_eq!(
+            responses.get(&samples).unwrap()[0].num_transactions,
+            245_412
+        );
+        assert_eq!(
+            responses.get(&epoch).unwrap_err().to_string(),
+            "RPC error -32601: Method not found"
+        );
+
+        let sent = sent(&http, 0);
+        assert_eq!(sent.as_array().unwrap().len(), 3);
+        assert_eq!(sent[0]["id"], 1);
+        assert_eq!(sent[1]["method"], "getRecentPerformanceSamples");
+        assert_eq!(sent[2]["params"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn batch_failures() {
+        let mut http = http(
+            MockTransport::new()
+                .respond(429, include_str!("../fixtures/rpc_rate_limited.json"))
+                .respond(200, r#"[{"jsonrpc":"2.0","result":1,"id":99}]"#),
+        );
+        let mut batch = RpcBatch::new();
+        batch.get_epoch_info().unwrap();
+        assert!(http
+            .send_batch(batch)
+            .unwrap_err()
+            .to_string()
+            .starts_with("RPC error 429"));
+
+        let mut batch = RpcBatch::new();
+        let epoch = batch.get_epoch_info().unwrap();
+        let responses = http.send_batch(batch).unwrap();
+        assert_eq!(
+            responses.get(&epoch).unwrap_err().to_string(),
+            "no response for getEpochInfo in the batch"
+        );
+    }
+
+    #[test]
+    fn token_accounts_are_requested_json_parsed() {
+        let mut http = http(MockTransport::new().respond(
+            200,
+            include_str!("../fixtures/get_token_accounts_by_owner.json"),
+        ));
+        let accounts = http
+            .get_token_accounts_by_owner("wallet", crate::tokens::TOKEN_2022_PROGRAM_ID)
+            .unwrap();
+        assert_eq!(accounts.len(), 4);
+        assert_eq!(accounts[2].account.data.program, "spl-token-2022");
+        assert_eq!(
+            accounts[2].account.data.parsed.info.token_amount,
+            TokenAmount {
+                amount: "25500000".to_string(),
+                decimals: 6,
+            }
+        );
+        assert_eq!(
+            sent(&http, 0)["params"],
+            serde_json::json!([
+                "wallet",
+                { "programId": "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb" },
+                { "encoding": "jsonParsed" }
+            ])
+        );
+    }
+
+    #[test]
+    fn epoch_progress_and_time_left() {
+        let mut http = http(
+            MockTransport::new().respond(200, include_str!("../fixtures/get_epoch_info.json")),
+        );
+        let epoch = http.get_epoch_info().unwrap();
+        assert_eq!((epoch.epoch, epoch.remaining_slots()), (730, 167_679));
+        assert!((epoch.progress() - 0.611_854).abs() < 1e-6);
+        assert_eq!(
+            epoch.time_remaining(Duration::from_millis(400)),
+            Duration::from_millis(67_071_600)
+        );
+    }
+}
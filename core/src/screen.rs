@@ -0,0 +1,734 @@
+use core::{cell::Cell, fmt::Debug};
+use std::time::{Duration, Instant};
+
+use crate::{
+    display::{token_pages, DisplayModule, Flush},
+    fetcher::Source,
+    lst::owner_sol_value,
+    playlist::{self, PlaylistError, DEFAULT_PLAYLIST},
+    portfolio::portfolio,
+    production::{EpochProduction, ProductionHistory},
+    rewards::epoch_length,
+    state::{Freshness, Metric, Snapshot},
+    wallets::Wallet,
+};
+
+/// One page of the rotation.
+pub trait Screen<D> {
+    /// Data the fetcher has to keep fresh for this screen.
+    fn sources(&self) -> &'static [Source] {
+        &[]
+    }
+
+    /// Draw the screen from the latest published data, `now` is used to
+    /// tell how old that data is.
+    fn render(&self, display: &mut DisplayModule<D>, snapshot: &Snapshot, now: Instant);
+
+    /// How long the screen stays up before moving on to the next one.
+    fn dwell(&self) -> Duration;
+}
+
+/// Balance of the wallet at `index` in the configured wallets.
+pub struct BalanceScreen {
+    pub index: usize,
+    pub label: String,
+    pub address: String,
+}
+
+impl BalanceScreen {
+    pub fn new(index: usize, wallet: &Wallet) -> Self {
+        Self {
+            index,
+            label: wallet.label.clone(),
+            address: wallet.address.clone(),
+        }
+    }
+}
+
+/// Balance of the wallet at `index`, nothing fetched yet when it has no slot.
+fn balance(snapshot: &Snapshot, index: usize) -> Metric<u64> {
+    snapshot.balances.get(index).cloned().unwrap_or_default()
+}
+
+impl<D> Screen<D> for BalanceScreen
+where
+    D: Flush,
+    D::Error: Debug,
+{
+    fn sources(&self) -> &'static [Source] {
+        // the token accounts and pool rates value the wallet's LSTs
+        &[Source::Balance, Source::Tokens, Source::LstRates]
+    }
+
+    fn render(&self, display: &mut DisplayModule<D>, snapshot: &Snapshot, now: Instant) {
+        let balance = balance(snapshot, self.index);
+        let lst_sol = owner_sol_value(
+            &self.address,
+            snapshot.lst_holdings.value.as_deref().unwrap_or_default(),
+            snapshot.lst_rates.value.as_deref().unwrap_or_default(),
+        );
+        display.draw_balance(&self.label, balance.value, lst_sol, snapshot.footer());
+        display.draw_freshness(balance.freshness(now, Source::Balance.max_age()));
+    }
+
+    fn dwell(&self) -> Duration {
+        Duration::from_millis(1500)
+    }
+}
+
+/// Every wallet balance on one page, plus their total.
+pub struct WalletsScreen {
+    pub labels: Vec<String>,
+}
+
+impl WalletsScreen {
+    pub fn new(wallets: &[Wallet]) -> Self {
+        Self {
+            labels: wallets.iter().map(|w| w.label.clone()).collect(),
+        }
+    }
+}
+
+impl<D> Screen<D> for WalletsScreen
+where
+    D: Flush,
+    D::Error: Debug,
+{
+    fn sources(&self) -> &'static [Source] {
+        &[Source::Balance]
+    }
+
+    fn render(&self, display: &mut DisplayModule<D>, snapshot: &Snapshot, now: Instant) {
+        let balances: Vec<_> = (0..self.labels.len())
+            .map(|i| balance(snapshot, i))
+            .collect();
+        let rows: Vec<_> = self
+            .labels
+            .iter()
+            .zip(&balances)
+            .map(|(label, balance)| (label.as_str(), balance.value))
+            .collect();
+        display.draw_wallets(&rows, snapshot.footer());
+
+        let max_age = Source::Balance.max_age();
+        display.draw_freshness(Freshness::stalest(
+            balances
+                .iter()
+                .map(|balance| balance.freshness(now, max_age)),
+        ));
+    }
+
+    fn dwell(&self) -> Duration {
+        Duration::from_secs(3)
+    }
+}
+
+/// The allowlisted SPL token balances, one page of rows at a time. Every
+/// time the screen comes up it scrolls to the next page.
+#[derive(Default)]
+pub struct TokensScreen {
+    page: Cell<usize>,
+}
+
+impl TokensScreen {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<D> Screen<D> for TokensScreen
+where
+    D: Flush,
+    D::Error: Debug,
+{
+    fn sources(&self) -> &'static [Source] {
+        &[Source::Tokens]
+    }
+
+    fn render(&self, display: &mut DisplayModule<D>, snapshot: &Snapshot, now: Instant) {
+        let tokens = &snapshot.tokens;
+        let pages = token_pages(tokens.value.as_ref().map_or(0, Vec::len));
+        // the list may have shrunk since the last time
+        let page = self.page.get() % pages;
+        self.page.set((page + 1) % pages);
+
+        display.draw_tokens(tokens.value.as_deref(), page, snapshot.footer());
+        display.draw_freshness(tokens.freshness(now, Source::Tokens.max_age()));
+    }
+
+    fn dwell(&self) -> Duration {
+        Duration::from_secs(3)
+    }
+}
+
+/// Value of SOL and the priced SPL tokens across every wallet, in the quote
+/// currency.
+pub struct PortfolioScreen;
+
+impl<D> Screen<D> for PortfolioScreen
+where
+    D: Flush,
+    D::Error: Debug,
+{
+    fn sources(&self) -> &'static [Source] {
+        &[
+            Source::Balance,
+            Source::Price,
+            Source::Tokens,
+            Source::TokenPrices,
+            Source::LstRates,
+        ]
+    }
+
+    fn render(&self, display: &mut DisplayModule<D>, snapshot: &Snapshot, now: Instant) {
+        display.draw_portfolio(
+            portfolio(snapshot).as_ref(),
+            &snapshot.quote,
+            snapshot.footer(),
+        );
+
+        let balances = snapshot
+            .balances
+            .iter()
+            .map(|balance| balance.freshness(now, Source::Balance.max_age()));
+        display.draw_freshness(Freshness::stalest(
+            balances.chain([
+                snapshot.price.freshness(now, Source::Price.max_age()),
+                snapshot.tokens.freshness(now, Source::Tokens.max_age()),
+                snapshot
+                    .token_prices
+                    .freshness(now, Source::TokenPrices.max_age()),
+                snapshot
+                    .lst_rates
+                    .freshness(now, Source::LstRates.max_age()),
+            ]),
+        ));
+    }
+
+    fn dwell(&self) -> Duration {
+        Duration::from_secs(3)
+    }
+}
+
+pub struct SlotTpsScreen;
+
+impl<D> Screen<D> for SlotTpsScreen
+where
+    D: Flush,
+    D::Error: Debug,
+{
+    fn sources(&self) -> &'static [Source] {
+        &[Source::Performance]
+    }
+
+    fn render(&self, display: &mut DisplayModule<D>, snapshot: &Snapshot, now: Instant) {
+        let performance = &snapshot.performance;
+        display.draw_slot_tps(performance.value, snapshot.footer());
+        display.draw_freshness(performance.freshness(now, Source::Performance.max_age()));
+    }
+
+    fn dwell(&self) -> Duration {
+        Duration::from_millis(1500)
+    }
+}
+
+/// Current epoch, how far into it the cluster is and when it ends.
+pub struct EpochScreen;
+
+impl<D> Screen<D> for EpochScreen
+where
+    D: Flush,
+    D::Error: Debug,
+{
+    fn sources(&self) -> &'static [Source] {
+        // the performance samples give the recent slot time for the ETA
+        &[Source::Epoch, Source::Performance]
+    }
+
+    fn render(&self, display: &mut DisplayModule<D>, snapshot: &Snapshot, now: Instant) {
+        let epoch = &snapshot.epoch;
+        let slot_time = snapshot.performance.value.and_then(|p| p.slot_time);
+        display.draw_epoch(epoch.value.as_ref(), slot_time, snapshot.footer());
+        display.draw_freshness(Freshness::stalest([
+            epoch.freshness(now, Source::Epoch.max_age()),
+            snapshot
+                .performance
+                .freshness(now, Source::Performance.max_age()),
+        ]));
+    }
+
+    fn dwell(&self) -> Duration {
+        Duration::from_secs(3)
+    }
+}
+
+/// The validator of the configured vote account.
+pub struct ValidatorScreen;
+
+impl<D> Screen<D> for ValidatorScreen
+where
+    D: Flush,
+    D::Error: Debug,
+{
+    fn sources(&self) -> &'static [Source] {
+        // the skip rate is the current epoch of the block production history
+        &[Source::Validator, Source::BlockProduction]
+    }
+
+    fn render(&self, display: &mut DisplayModule<D>, snapshot: &Snapshot, now: Instant) {
+        let validator = &snapshot.validator;
+        let production = &snapshot.block_production;
+        let skip_rate = production
+            .value
+            .as_ref()
+            .and_then(ProductionHistory::current)
+            .and_then(EpochProduction::skip_rate);
+        display.draw_validator(validator.value.as_ref(), skip_rate, snapshot.footer());
+        display.draw_freshness(Freshness::stalest([
+            validator.freshness(now, Source::Validator.max_age()),
+            production.freshness(now, Source::BlockProduction.max_age()),
+        ]));
+    }
+
+    fn dwell(&self) -> Duration {
+        Duration::from_secs(3)
+    }
+}
+
+/// Countdown to the next leader slots of the configured validator.
+pub struct LeaderScreen;
+
+impl<D> Screen<D> for LeaderScreen
+where
+    D: Flush,
+    D::Error: Debug,
+{
+    fn sources(&self) -> &'static [Source] {
+        // the schedule needs the validator identity, the countdown the slot time
+        &[
+            Source::LeaderSchedule,
+            Source::Validator,
+            Source::Performance,
+        ]
+    }
+
+    fn render(&self, display: &mut DisplayModule<D>, snapshot: &Snapshot, now: Instant) {
+        let leader = &snapshot.leader;
+        let slot_time = snapshot.performance.value.and_then(|p| p.slot_time);
+        display.draw_leader(leader.value.as_ref(), slot_time, snapshot.footer());
+        display.draw_freshness(Freshness::stalest([
+            leader.freshness(now, Source::LeaderSchedule.max_age()),
+            snapshot
+                .performance
+                .freshness(now, Source::Performance.max_age()),
+        ]));
+    }
+
+    fn dwell(&self) -> Duration {
+        Duration::from_secs(3)
+    }
+}
+
+/// Block production of the configured validator over the last epochs.
+pub struct BlockProductionScreen;
+
+impl<D> Screen<D> for BlockProductionScreen
+where
+    D: Flush,
+    D::Error: Debug,
+{
+    fn sources(&self) -> &'static [Source] {
+        &[Source::BlockProduction, Source::Validator]
+    }
+
+    fn render(&self, display: &mut DisplayModule<D>, snapshot: &Snapshot, now: Instant) {
+        let production = &snapshot.block_production;
+        display.draw_block_production(production.value.as_ref(), snapshot.footer());
+        display.draw_freshness(production.freshness(now, Source::BlockProduction.max_age()));
+    }
+
+    fn dwell(&self) -> Duration {
+        Duration::from_secs(3)
+    }
+}
+
+/// Staked, activating and deactivating SOL of the watched wallets.
+pub struct StakeScreen;
+
+impl<D> Screen<D> for StakeScreen
+where
+    D: Flush,
+    D::Error: Debug,
+{
+    fn sources(&self) -> &'static [Source] {
+        &[Source::Stake]
+    }
+
+    fn render(&self, display: &mut DisplayModule<D>, snapshot: &Snapshot, now: Instant) {
+        let stake = &snapshot.stake;
+        display.draw_stake(stake.value.as_ref(), snapshot.footer());
+        display.draw_freshness(stake.freshness(now, Source::Stake.max_age()));
+    }
+
+    fn dwell(&self) -> Duration {
+        Duration::from_secs(3)
+    }
+}
+
+/// Staking rewards of the last epochs as a bar chart, with the estimated APY.
+pub struct RewardsScreen;
+
+impl<D> Screen<D> for RewardsScreen
+where
+    D: Flush,
+    D::Error: Debug,
+{
+    fn sources(&self) -> &'static [Source] {
+        // the stake accounts tell which epoch the rewards are due for, the
+        // slots per epoch and the recent slot time give the epoch length for
+        // the APY
+        &[
+            Source::Rewards,
+            Source::Stake,
+            Source::Epoch,
+            Source::Performance,
+        ]
+    }
+
+    fn render(&self, display: &mut DisplayModule<D>, snapshot: &Snapshot, now: Instant) {
+        let rewards = &snapshot.rewards;
+        let slot_time = snapshot.performance.value.and_then(|p| p.slot_time);
+        let epoch_length = epoch_length(snapshot.epoch.value.as_ref(), slot_time);
+        display.draw_rewards(rewards.value.as_ref(), epoch_length, snapshot.footer());
+        // fetched once per epoch, so only a failed fetch makes them stale
+        display.draw_freshness(Freshness::stalest([
+            rewards.freshness(now, Duration::MAX),
+            snapshot.stake.freshness(now, Source::Stake.max_age()),
+        ]));
+    }
+
+    fn dwell(&self) -> Duration {
+        Duration::from_secs(3)
+    }
+}
+
+pub struct PriceScreen;
+
+impl<D> Screen<D> for PriceScreen
+where
+    D: Flush,
+    D::Error: Debug,
+{
+    fn sources(&self) -> &'static [Source] {
+        &[Source::Price]
+    }
+
+    fn render(&self, display: &mut DisplayModule<D>, snapshot: &Snapshot, now: Instant) {
+        let price = &snapshot.price;
+        let history: Vec<f64> = snapshot.price_history.iter().collect();
+        display.draw_price(price.value, &history, &snapshot.quote, snapshot.footer());
+        display.draw_freshness(price.freshness(now, Source::Price.max_age()));
+    }
+
+    fn dwell(&self) -> Duration {
+        Duration::from_millis(1500)
+    }
+}
+
+/// QR code of a wallet address, so it can be scanned from a phone. Every
+/// time the page comes up it shows the next wallet.
+pub struct QrScreen {
+    pub wallets: Vec<Wallet>,
+    next: Cell<usize>,
+}
+
+impl QrScreen {
+    pub fn new(wallets: &[Wallet]) -> Self {
+        Self {
+            wallets: wallets.to_vec(),
+            next: Cell::new(0),
+        }
+    }
+}
+
+impl<D> Screen<D> for QrScreen
+where
+    D: Flush,
+    D::Error: Debug,
+{
+    fn render(&self, display: &mut DisplayModule<D>, _snapshot: &Snapshot, _now: Instant) {
+        display.create_black_rectangle();
+        let Some(wallet) = self.wallets.get(self.next.get()) else {
+            return;
+        };
+        self.next.set((self.next.get() + 1) % self.wallets.len());
+        display.draw_labeled_qr_code(&wallet.label, &wallet.address);
+    }
+
+    fn dwell(&self) -> Duration {
+        Duration::from_secs(6)
+    }
+}
+
+/// Build the screens registered under a playlist name, `balance` gives one
+/// screen per wallet.
+fn screens_for<D>(name: &str, wallets: &[Wallet]) -> Option<Vec<Box<dyn Screen<D>>>>
+where
+    D: Flush,
+    D::Error: Debug,
+{
+    let screen: Box<dyn Screen<D>> = match name {
+        "balance" => {
+            return Some(
+                wallets
+                    .iter()
+                    .enumerate()
+                    .map(|(i, wallet)| {
+                        Box::new(BalanceScreen::new(i, wallet)) as Box<dyn Screen<D>>
+                    })
+                    .collect(),
+            )
+        }
+        "wallets" => Box::new(WalletsScreen::new(wallets)),
+        "tokens" => Box::new(TokensScreen::new()),
+        "portfolio" => Box::new(PortfolioScreen),
+        "slots" => Box::new(SlotTpsScreen),
+        "epoch" => Box::new(EpochScreen),
+        "validator" => Box::new(ValidatorScreen),
+        "leader" => Box::new(LeaderScreen),
+        "blocks" => Box::new(BlockProductionScreen),
+        "stake" => Box::new(StakeScreen),
+        "rewards" => Box::new(RewardsScreen),
+        "price" => Box::new(PriceScreen),
+        "qr" => Box::new(QrScreen::new(wallets)),
+        _ => return None,
+    };
+    Some(vec![screen])
+}
+
+struct Slot<D> {
+    screen: Box<dyn Screen<D>>,
+    /// Overrides the screen's own dwell time when set.
+    dwell: Option<Duration>,
+}
+
+/// Cycles through a list of screens, one screen per [`Rotation::show_next`].
+pub struct Rotation<D> {
+    screens: Vec<Slot<D>>,
+    next: usize,
+}
+
+impl<D> Rotation<D>
+where
+    D: Flush,
+    D::Error: Debug,
+{
+    pub fn new(screens: Vec<Box<dyn Screen<D>>>) -> Self {
+        Self {
+            screens: screens
+                .into_iter()
+                .map(|screen| Slot {
+                    screen,
+                    dwell: None,
+                })
+                .collect(),
+            next: 0,
+        }
+    }
+
+    /// Build the rotation described by a playlist, see [`playlist`].
+    pub fn from_playlist(playlist: &str, wallets: &[Wallet]) -> Result<Self, PlaylistError> {
+        let mut rotation = Self::new(Vec::new());
+        for entry in playlist::parse(playlist)? {
+            let screens = screens_for(entry.name, wallets).ok_or(PlaylistError::UnknownScreen)?;
+            for screen in screens {
+                rotation.push(screen, entry.dwell);
+            }
+        }
+        Ok(rotation)
+    }
+
+    /// The default pages: balances, slot/TPS, SOL price and the wallet QR code.
+    pub fn default_screens(wallets: &[Wallet]) -> Self {
+        Self::from_playlist(DEFAULT_PLAYLIST, wallets).expect("the default playlist is valid")
+    }
+
+    /// Append a screen, `dwell` overrides the screen's own dwell time.
+    pub fn push(&mut self, screen: Box<dyn Screen<D>>, dwell: Option<Duration>) {
+        self.screens.push(Slot { screen, dwell });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.screens.is_empty()
+    }
+
+    /// Every source the screens need, plus the footer clock.
+    pub fn sources(&self) -> Vec<Source> {
+        let mut sources = vec![Source::Clock];
+        for slot in &self.screens {
+            for source in slot.screen.sources() {
+                if !sources.contains(source) {
+                    sources.push(*source);
+                }
+            }
+        }
+        sources
+    }
+
+    /// Draw the next screen, returning how long it should stay up.
+    pub fn show_next(
+        &mut self,
+        display: &mut DisplayModule<D>,
+        snapshot: &Snapshot,
+        now: Instant,
+    ) -> Duration {
+        if self.screens.is_empty() {
+            return Duration::ZERO;
+        }
+
+        let Slot { screen, dwell } = &self.screens[self.next];
+        screen.render(display, snapshot, now);
+        self.next = (self.next + 1) % self.screens.len();
+        dwell.unwrap_or_else(|| screen.dwell())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        framebuffer::Framebuffer,
+        simulator::{sample_snapshot, sample_wallets, SAMPLE_WALLET},
+    };
+
+    fn one_wallet() -> Vec<Wallet> {
+        vec![Wallet::new("Wallet", SAMPLE_WALLET)]
+    }
+
+    #[test]
+    fn rotation_cycles_screens_in_order() {
+        let mut display = DisplayModule::new(Framebuffer::new());
+        let mut rotation = Rotation::default_screens(&one_wallet());
+        let snapshot = Snapshot::default();
+
+        let dwells: Vec<_> = (0..5)
+            .map(|_| rotation.show_next(&mut display, &snapshot, Instant::now()))
+            .collect();
+        assert_eq!(
+            dwells,
+            [1500, 1500, 1500, 6000, 1500].map(Duration::from_millis)
+        );
+        assert_eq!(
+            rotation.sources(),
+            [
+                Source::Clock,
+                Source::Balance,
+                Source::Tokens,
+                Source::LstRates,
+                Source::Performance,
+                Source::Price
+            ]
+        );
+    }
+
+    #[test]
+    fn every_playlist_name_has_a_screen() {
+        for name in playlist::SCREEN_NAMES {
+            assert!(screens_for::<Framebuffer>(name, &one_wallet()).is_some());
+        }
+    }
+
+    #[test]
+    fn playlist_sets_order_dwell_and_sources() {
+        let mut display = DisplayModule::new(Framebuffer::new());
+        let mut rotation = Rotation::from_playlist("qr=2500, price", &one_wallet()).unwrap();
+        let snapshot = Snapshot::default();
+
+        assert_eq!(
+            rotation.show_next(&mut display, &snapshot, Instant::now()),
+            Duration::from_millis(2500)
+        );
+        assert_eq!(
+            rotation.show_next(&mut display, &snapshot, Instant::now()),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(rotation.sources(), [Source::Clock, Source::Price]);
+        assert!(Rotation::<Framebuffer>::from_playlist("weather", &one_wallet()).is_err());
+    }
+
+    #[test]
+    fn balance_expands_to_one_page_per_wallet() {
+        let wallets = sample_wallets();
+        let rotation =
+            Rotation::<Framebuffer>::from_playlist("balance=2000, wallets", &wallets).unwrap();
+        let dwells: Vec<_> = rotation
+            .screens
+            .iter()
+            .map(|slot| slot.dwell.unwrap_or_else(|| slot.screen.dwell()))
+            .collect();
+        assert_eq!(dwells, [2000, 2000, 2000, 3000].map(Duration::from_millis));
+    }
+
+    #[test]
+    fn balance_values_the_lsts_on_its_own() {
+        let rotation = Rotation::<Framebuffer>::from_playlist("balance", &one_wallet()).unwrap();
+        assert_eq!(
+            rotation.sources(),
+            [
+                Source::Clock,
+                Source::Balance,
+                Source::Tokens,
+                Source::LstRates
+            ]
+        );
+    }
+
+    #[test]
+    fn qr_cycles_through_the_wallets() {
+        let wallets = sample_wallets();
+        let qr = QrScreen::new(&wallets);
+        let snapshot = sample_snapshot(Instant::now());
+        let render = || {
+            let mut display = DisplayModule::new(Framebuffer::new());
+            Screen::render(&qr, &mut display, &snapshot, Instant::now());
+            display.display
+        };
+        let frames: Vec<_> = (0..=wallets.len()).map(|_| render()).collect();
+        assert!(frames[0] != frames[1]);
+        assert!(frames[1] != frames[2]);
+        assert!(frames[0] == frames[3]);
+    }
+
+    #[test]
+    fn token_list_scrolls_one_page_per_showing() {
+        let tokens = TokensScreen::new();
+        let mut snapshot = sample_snapshot(Instant::now());
+        let render = |snapshot: &Snapshot| {
+            let mut display = DisplayModule::new(Framebuffer::new());
+            Screen::render(&tokens, &mut display, snapshot, Instant::now());
+            display.display
+        };
+        // five tokens take two pages
+        let first = render(&snapshot);
+        let second = render(&snapshot);
+        assert!(first != second);
+        assert!(render(&snapshot) == first);
+
+        // back on the first page once the list fits on one
+        render(&snapshot);
+        snapshot.tokens.value.as_mut().unwrap().truncate(2);
+        let single = render(&snapshot);
+        assert!(render(&snapshot) == single);
+    }
+
+    #[test]
+    fn empty_rotation_does_nothing() {
+        let mut display = DisplayModule::new(Framebuffer::new());
+        let mut rotation = Rotation::new(Vec::new());
+        assert!(rotation.is_empty());
+        assert_eq!(
+            rotation.show_next(&mut display, &Snapshot::default(), Instant::now()),
+            Duration::ZERO
+        );
+    }
+}
@@ -0,0 +1,423 @@
+//! Renders every screen of the rotation with fixed sample data, used by the
+//! `render_screens` example and the golden-image snapshot tests.
+
+use std::time::{Duration, Instant};
+
+use crate::{
+    alerts::{self, Alert, AlertMonitor},
+    currency::{self, USD},
+    display::DisplayModule,
+    framebuffer::Framebuffer,
+    history::{RingBuffer, PRICE_HISTORY_LEN},
+    http::LAMPORTS_PER_SOL,
+    leader::{LeaderSchedule, LEADER_RUN_SLOTS},
+    lst::{LstHolding, LstRate, LSTS},
+    production::{EpochProduction, ProductionHistory},
+    rewards::{EpochReward, RewardHistory, REWARDS_EPOCHS},
+    rpc::EpochInfo,
+    screen::{
+        BalanceScreen, BlockProductionScreen, EpochScreen, LeaderScreen, PortfolioScreen,
+        PriceScreen, QrScreen, RewardsScreen, Screen, SlotTpsScreen, StakeScreen, TokensScreen,
+        ValidatorScreen, WalletsScreen,
+    },
+    stake::{Delegation, StakeAccount, StakeAccounts},
+    state::{Clock, Metric, OracleQuote, Performance, Snapshot, SolPrice},
+    tokens::{TokenBalance, TokenPrice},
+    validator::ValidatorStatus,
+    wallets::Wallet,
+};
+
+pub const SAMPLE_WALLET: &str = "aKgfWjGePnbFgDAuCqxB5oymuFxQskvCtrw6eYfDa7fg";
+
+/// A treasury, a hot wallet and a fee payer, matching the balances of
+/// [`sample_snapshot`].
+pub fn sample_wallets() -> Vec<Wallet> {
+    vec![
+        Wallet::new("Treasury", SAMPLE_WALLET),
+        Wallet::new("Hot", "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM"),
+        Wallet::new("Fee payer", "HN7cABqLq46Es1jh92dQQisAq662SmxELLLsHHe4YWrH"),
+    ]
+}
+
+fn fetched<T>(value: T, fetched_at: Instant) -> Metric<T> {
+    Metric {
+        value: Some(value),
+        fetched_at: Some(fetched_at),
+        last_error: None,
+    }
+}
+
+fn token(symbol: &str, mint: &str, amount: u64, decimals: u8) -> TokenBalance {
+    TokenBalance {
+        symbol: symbol.to_string(),
+        mint: mint.to_string(),
+        amount,
+        decimals,
+    }
+}
+
+/// Five allowlisted tokens, one more than fit on a page of the token list.
+pub fn sample_tokens() -> Vec<TokenBalance> {
+    vec![
+        token(
+            "USDC",
+            "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+            1_250_750_000,
+            6,
+        ),
+        token(
+            "PYUSD",
+            "2b1kV6DkPAnxd5ixfnxCpjxmKwqjjaYmCZfHsFu24GXo",
+            25_500_000,
+            6,
+        ),
+        token(
+            "JUP",
+            "JUPyiwrYJFskUPiHa7hkeR8VUtAeFoSYbKedZNsDvCN",
+            431_080_000,
+            6,
+        ),
+        token(
+            "BONK",
+            "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263",
+            25_000_000_000_000,
+            5,
+        ),
+        token("JTO", "jtojtomepa8beP8AuQc6eXt5FriJwfFMwQx2v2f9mCL", 0, 9),
+    ]
+}
+
+/// Prices for every sample token but JTO, which is left unpriced.
+pub fn sample_token_prices() -> Vec<TokenPrice> {
+    sample_tokens()
+        .into_iter()
+        .zip([1.0, 1.0, 0.7512, 0.000021])
+        .map(|(token, price)| TokenPrice {
+            mint: token.mint,
+            price,
+        })
+        .collect()
+}
+
+/// mSOL, jitoSOL and bSOL rates as in the pool state fixtures.
+pub fn sample_lst_rates() -> Vec<LstRate> {
+    LSTS.iter()
+        .zip([1.2845, 1.1872, 1.2531])
+        .map(|(lst, sol)| LstRate {
+            mint: lst.mint.to_string(),
+            sol,
+        })
+        .collect()
+}
+
+/// 2.5 mSOL and 1.2 jitoSOL in the treasury, as in the LST token accounts
+/// fixture.
+pub fn sample_lst_holdings() -> Vec<LstHolding> {
+    let [msol, jitosol, _] = LSTS;
+    [(msol, 2_500_000_000), (jitosol, 1_200_000_000)]
+        .into_iter()
+        .map(|(lst, amount)| LstHolding {
+            owner: SAMPLE_WALLET.to_string(),
+            balance: token(lst.symbol, lst.mint, amount, 9),
+        })
+        .collect()
+}
+
+/// Vote account and identity of the validator in [`sample_validator`].
+pub const SAMPLE_VOTE_ACCOUNT: &str = "Vote4pGwNfHrXk2u9pfTzSLFkw5PqHoDyWYLzW7Zn8eR";
+pub const SAMPLE_VOTE_IDENTITY: &str = "Node7QxjmD3hTuZbVf8yKwRcN2aPLe5sG9HvB4xEq1Mt";
+
+/// A voting validator with 412k SOL of stake, as in the vote account fixture.
+pub fn sample_validator() -> ValidatorStatus {
+    ValidatorStatus {
+        vote_pubkey: SAMPLE_VOTE_ACCOUNT.to_string(),
+        node_pubkey: SAMPLE_VOTE_IDENTITY.to_string(),
+        activated_stake: 412_345_678_901_234,
+        commission: 5,
+        last_vote: 315_482_097,
+        slot: 315_482_100,
+        delinquent: false,
+    }
+}
+
+/// Leader runs of the sample validator in epoch 730, from the first slot of the
+/// epoch. The sample slot is 264321 slots in.
+const SAMPLE_LEADER_RUNS: [u64; 12] = [
+    12_000, 48_000, 96_000, 150_000, 200_000, 250_000, 264_620, 290_000, 330_000, 370_000, 400_000,
+    431_996,
+];
+
+/// The leader schedule in the leader schedule fixture, next leader in 299 slots.
+pub fn sample_leader_schedule() -> LeaderSchedule {
+    let first_slot = 315_217_779;
+    LeaderSchedule {
+        epoch: 730,
+        first_slot,
+        last_slot: first_slot + 431_999,
+        slots: SAMPLE_LEADER_RUNS
+            .iter()
+            .flat_map(|&start| (0..LEADER_RUN_SLOTS as u64).map(move |i| first_slot + start + i))
+            .collect(),
+        slot: 315_482_100,
+    }
+}
+
+/// Three closed epochs and the current one, matching the block production
+/// fixture.
+pub fn sample_production_history() -> ProductionHistory {
+    let epoch = |epoch, leader_slots, blocks_produced| EpochProduction {
+        epoch,
+        leader_slots,
+        blocks_produced,
+    };
+    ProductionHistory {
+        epochs: vec![
+            epoch(727, 1_624, 1_611),
+            epoch(728, 1_588, 1_587),
+            epoch(729, 1_600, 1_548),
+            epoch(730, 1_200, 1_176),
+        ],
+    }
+}
+
+/// The stake accounts in the stake program accounts fixture, in epoch 730: 100
+/// SOL active, 25 activating, 10 deactivating and 5 never delegated.
+pub fn sample_stake_accounts() -> StakeAccounts {
+    const SOL: u64 = LAMPORTS_PER_SOL as u64;
+    const RESERVE: u64 = 2_282_880;
+    let account = |pubkey: &str, sol: u64, epochs: Option<(u64, u64)>| StakeAccount {
+        pubkey: pubkey.to_string(),
+        lamports: sol * SOL + RESERVE,
+        rent_exempt_reserve: RESERVE,
+        delegation: epochs.map(|(activation_epoch, deactivation_epoch)| Delegation {
+            stake: sol * SOL,
+            activation_epoch,
+            deactivation_epoch,
+        }),
+    };
+    StakeAccounts {
+        epoch: 730,
+        accounts: vec![
+            account(
+                "B8c4w3XNBNudakPyUiAAnPRr3XC5WynuCTPZiAV9MsmG",
+                100,
+                Some((700, u64::MAX)),
+            ),
+            account(
+                "7B7YbYR3H7zFhFTBY8FRd6uTuKitm7pEc4NuD3DAVMYh",
+                25,
+                Some((730, u64::MAX)),
+            ),
+            account(
+                "HtzNuLs8ZcRaFt3a8hBVNqeYw1xH6nRPpXrdQiUuCUH3",
+                10,
+                Some((650, 730)),
+            ),
+            account("2htvADws9BNdWPM645wucS2H16t6WYbmmY2D6kcwASEY", 5, None),
+        ],
+    }
+}
+
+/// Rewards of the sample stake accounts over the last ten epochs, about 6.9%
+/// APY. The last epoch matches the inflation reward fixture.
+pub fn sample_reward_history() -> RewardHistory {
+    const AMOUNTS: [u64; REWARDS_EPOCHS] = [
+        40_120_000, 40_910_000, 39_870_000, 41_350_000, 40_480_000, 38_900_000, 41_020_000,
+        40_640_000, 39_990_000, 40_760_500,
+    ];
+    RewardHistory {
+        epochs: (720..)
+            .zip(AMOUNTS)
+            .map(|(epoch, amount)| EpochReward {
+                epoch,
+                amount,
+                staked: 110_004_565_760,
+            })
+            .collect(),
+    }
+}
+
+/// Two hours of SOL prices drifting up to the sample price of 187.42.
+pub fn sample_price_history() -> RingBuffer<f64, PRICE_HISTORY_LEN> {
+    let mut history = RingBuffer::default();
+    for i in 1..PRICE_HISTORY_LEN {
+        let i = i as f64;
+        history.push(183.0 + i * 0.035 + 1.2 * (i / 7.0).sin());
+    }
+    history.push(187.42);
+    history
+}
+
+/// Every metric fetched successfully at `fetched_at`.
+pub fn sample_snapshot(fetched_at: Instant) -> Snapshot {
+    Snapshot {
+        quote: USD,
+        epoch: fetched(
+            EpochInfo {
+                absolute_slot: 315_482_100,
+                block_height: 293_650_871,
+                epoch: 730,
+                slot_index: 264_321,
+                slots_in_epoch: 432_000,
+                transaction_count: None,
+            },
+            fetched_at,
+        ),
+        balances: vec![
+            fetched(12 * LAMPORTS_PER_SOL as u64 + 345_678_901, fetched_at),
+            fetched(3 * LAMPORTS_PER_SOL as u64 + 200_000_000, fetched_at),
+            fetched(42_100_000, fetched_at),
+        ],
+        performance: fetched(
+            Performance {
+                slot: 315_482_100,
+                tps: 4_090,
+                non_vote_tps: Some(1_039),
+                slot_time: Some(Duration::from_millis(400)),
+            },
+            fetched_at,
+        ),
+        price: fetched(
+            SolPrice {
+                price: 187.42,
+                change_24h: Some(2.31),
+                oracle: None,
+            },
+            fetched_at,
+        ),
+        price_history: sample_price_history(),
+        tokens: fetched(sample_tokens(), fetched_at),
+        token_prices: fetched(sample_token_prices(), fetched_at),
+        lst_holdings: fetched(sample_lst_holdings(), fetched_at),
+        lst_rates: fetched(sample_lst_rates(), fetched_at),
+        clock: fetched(
+            Clock {
+                time: "09:05:03".to_string(),
+                date: "2025-01-07".to_string(),
+            },
+            fetched_at,
+        ),
+        validator: fetched(sample_validator(), fetched_at),
+        leader: fetched(sample_leader_schedule(), fetched_at),
+        block_production: fetched(sample_production_history(), fetched_at),
+        stake: fetched(sample_stake_accounts(), fetched_at),
+        rewards: fetched(sample_reward_history(), fetched_at),
+    }
+}
+
+fn render(screen: &dyn Screen<Framebuffer>, snapshot: &Snapshot, now: Instant) -> Framebuffer {
+    let mut module = DisplayModule::new(Framebuffer::new());
+    screen.render(&mut module, snapshot, now);
+    module.display
+}
+
+/// `(name, framebuffer)` for each screen, in display order, followed by the
+/// stale and missing data variants.
+pub fn render_screens() -> Vec<(&'static str, Framebuffer)> {
+    let now = Instant::now();
+    let snapshot = sample_snapshot(now);
+    let mut off = DisplayModule::new(Framebuffer::new());
+    off.create_black_rectangle();
+    off.draw_image();
+
+    let mut alert = DisplayModule::new(Framebuffer::new());
+    let mut monitor = AlertMonitor::new(
+        alerts::parse("price>180, Hot<5", &sample_wallets()).expect("valid sample alerts"),
+    );
+    monitor.check(&snapshot);
+    alert.draw_alert(monitor.latched(), false);
+
+    // the validator stopped voting a minute ago
+    let mut delinquent = snapshot.clone();
+    delinquent.validator = fetched(
+        ValidatorStatus {
+            last_vote: 315_481_950,
+            delinquent: true,
+            ..sample_validator()
+        },
+        now,
+    );
+    let mut delinquency = DisplayModule::new(Framebuffer::new());
+    let mut monitor = AlertMonitor::new(vec![Alert::delinquency()]);
+    monitor.check(&delinquent);
+    delinquency.draw_alert(monitor.latched(), false);
+
+    // the RPC has been failing for five minutes, the price never loaded
+    let mut degraded = snapshot.clone();
+    degraded.balances[0].last_error = Some("HTTP Error: Status code 429".to_string());
+    degraded.price = Metric {
+        last_error: Some("connection reset".to_string()),
+        ..Metric::default()
+    };
+    let five_minutes_later = now + Duration::from_secs(5 * 60);
+
+    // the price read from the Pyth feed instead of a REST API
+    let mut on_chain = snapshot.clone();
+    on_chain.price = fetched(
+        SolPrice {
+            price: 187.42,
+            change_24h: None,
+            oracle: Some(OracleQuote {
+                confidence: 0.095,
+                publish_time: 1_736_240_703,
+            }),
+        },
+        now,
+    );
+
+    let mut in_euros = snapshot.clone();
+    in_euros.quote = currency::find("eur").expect("EUR is a quote currency");
+    in_euros.price = fetched(
+        SolPrice {
+            price: 172.1,
+            change_24h: Some(-0.85),
+            oracle: None,
+        },
+        now,
+    );
+    in_euros.price_history = RingBuffer::default();
+    for price in sample_price_history().iter() {
+        in_euros.price_history.push(price * 0.918);
+    }
+
+    let wallets = sample_wallets();
+    let treasury = BalanceScreen::new(0, &wallets[0]);
+    let tokens = TokensScreen::new();
+
+    vec![
+        ("balance", render(&treasury, &snapshot, now)),
+        (
+            "wallets",
+            render(&WalletsScreen::new(&wallets), &snapshot, now),
+        ),
+        ("tokens", render(&tokens, &snapshot, now)),
+        ("tokens_page_2", render(&tokens, &snapshot, now)),
+        ("portfolio", render(&PortfolioScreen, &snapshot, now)),
+        ("slot_tps", render(&SlotTpsScreen, &snapshot, now)),
+        ("epoch", render(&EpochScreen, &snapshot, now)),
+        ("validator", render(&ValidatorScreen, &snapshot, now)),
+        (
+            "validator_delinquent",
+            render(&ValidatorScreen, &delinquent, now),
+        ),
+        ("leader", render(&LeaderScreen, &snapshot, now)),
+        ("blocks", render(&BlockProductionScreen, &snapshot, now)),
+        ("stake", render(&StakeScreen, &snapshot, now)),
+        ("rewards", render(&RewardsScreen, &snapshot, now)),
+        ("price", render(&PriceScreen, &snapshot, now)),
+        ("price_pyth", render(&PriceScreen, &on_chain, now)),
+        ("price_eur", render(&PriceScreen, &in_euros, now)),
+        ("qr", render(&QrScreen::new(&wallets), &snapshot, now)),
+        ("off", off.display),
+        ("alert", alert.display),
+        ("alert_delinquent", delinquency.display),
+        (
+            "balance_stale",
+            render(&treasury, &degraded, five_minutes_later),
+        ),
+        (
+            "price_missing",
+            render(&PriceScreen, &degraded, five_minutes_later),
+        ),
+    ]
+}
@@ -0,0 +1,247 @@
+//! Stake accounts of the watched wallets.
+//!
+//! Stake accounts are found with `getProgramAccounts` on the Stake program,
+//! matching a wallet as the staker or the withdrawer of the account. The
+//! account data is a bincode `StakeStateV2`: a `u32` tag, the `Meta` with the
+//! rent reserve and the authorities, and for a delegated account the
+//! `Delegation` with its stake and (de)activation epochs.
+
+use std::error::Error;
+
+use crate::{
+    reader::Reader,
+    rpc::{AccountInfo, ProgramAccount},
+};
+
+pub const STAKE_PROGRAM_ID: &str = "Stake11111111111111111111111111111111111111";
+
+/// Size of every stake account.
+pub const STAKE_ACCOUNT_LEN: usize = 200;
+
+/// Offsets of the staker and withdrawer authorities in the account data.
+pub const STAKER_OFFSET: usize = 12;
+pub const WITHDRAWER_OFFSET: usize = 44;
+
+/// Deactivation epoch of a delegation that was never deactivated.
+const NEVER: u64 = u64::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StakeState {
+    Inactive,
+    Activating,
+    Active,
+    Deactivating,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Delegation {
+    /// Delegated lamports.
+    pub stake: u64,
+    pub activation_epoch: u64,
+    pub deactivation_epoch: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StakeAccount {
+    pub pubkey: String,
+    pub lamports: u64,
+    pub rent_exempt_reserve: u64,
+    /// `None` for an initialized account that was never delegated.
+    pub delegation: Option<Delegation>,
+}
+
+impl StakeAccount {
+    /// Decode the stake account at `pubkey`.
+    pub fn decode(pubkey: &str, account: &AccountInfo) -> Result<Self, Box<dyn Error>> {
+        if account.owner != STAKE_PROGRAM_ID {
+            return Err(format!("{} is not a stake account", pubkey).into());
+        }
+        let data = account.decode_data()?;
+        let mut reader = Reader::new(&data, "stake account");
+        let tag = reader.u32()?;
+        if !matches!(tag, 1 | 2) {
+            return Err(format!("stake account {} is not initialized", pubkey).into());
+        }
+        let rent_exempt_reserve = reader.u64()?;
+        let _authorized = reader.take::<64>()?;
+        let _lockup = reader.take::<48>()?;
+        let delegation = match tag {
+            2 => {
+                let _voter = reader.take::<32>()?;
+                Some(Delegation {
+                    stake: reader.u64()?,
+                    activation_epoch: reader.u64()?,
+                    deactivation_epoch: reader.u64()?,
+                })
+            }
+            _ => None,
+        };
+        Ok(Self {
+            pubkey: pubkey.to_string(),
+            lamports: account.lamports,
+            rent_exempt_reserve,
+            delegation,
+        })
+    }
+
+    /// State in `epoch`. The warmup and cooldown rate limit is left out, so a
+    /// delegation counts as fully (de)activated one epoch after the change.
+    pub fn state(&self, epoch: u64) -> StakeState {
+        let Some(delegation) = self.delegation else {
+            return StakeState::Inactive;
+        };
+        if delegation.activation_epoch == delegation.deactivation_epoch {
+            StakeState::Inactive
+        } else if epoch <= delegation.activation_epoch {
+            StakeState::Activating
+        } else if delegation.deactivation_epoch == NEVER {
+            StakeState::Active
+        } else if epoch <= delegation.deactivation_epoch {
+            StakeState::Deactivating
+        } else {
+            StakeState::Inactive
+        }
+    }
+
+    /// The delegated stake, or for inactive stake everything above the rent
+    /// reserve, which is what can be withdrawn.
+    pub fn amount(&self, epoch: u64) -> u64 {
+        match (self.state(epoch), self.delegation) {
+            (StakeState::Inactive, _) | (_, None) => {
+                self.lamports.saturating_sub(self.rent_exempt_reserve)
+            }
+            (_, Some(delegation)) => delegation.stake,
+        }
+    }
+}
+
+/// Lamports in each state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StakeTotals {
+    pub active: u64,
+    pub activating: u64,
+    pub deactivating: u64,
+    pub inactive: u64,
+}
+
+impl StakeTotals {
+    pub fn total(&self) -> u64 {
+        self.active + self.activating + self.deactivating + self.inactive
+    }
+}
+
+/// Stake accounts of every watched wallet as of `epoch`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StakeAccounts {
+    pub epoch: u64,
+    pub accounts: Vec<StakeAccount>,
+}
+
+impl StakeAccounts {
+    /// Decode the accounts found for every wallet and authority, an account
+    /// found more than once is counted once.
+    pub fn new(epoch: u64, found: Vec<Vec<ProgramAccount>>) -> Result<Self, Box<dyn Error>> {
+        let mut accounts: Vec<StakeAccount> = Vec::new();
+        for account in found.into_iter().flatten() {
+            if accounts.iter().all(|a| a.pubkey != account.pubkey) {
+                accounts.push(StakeAccount::decode(&account.pubkey, &account.account)?);
+            }
+        }
+        Ok(Self { epoch, accounts })
+    }
+
+    pub fn totals(&self) -> StakeTotals {
+        let mut totals = StakeTotals::default();
+        for account in &self.accounts {
+            let amount = account.amount(self.epoch);
+            match account.state(self.epoch) {
+                StakeState::Active => totals.active += amount,
+                StakeState::Activating => totals.activating += amount,
+                StakeState::Deactivating => totals.deactivating += amount,
+                StakeState::Inactive => totals.inactive += amount,
+            }
+        }
+        totals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{http::LAMPORTS_PER_SOL, rpc::RpcResponse};
+
+    const SOL: u64 = LAMPORTS_PER_SOL as u64;
+
+    fn found() -> Vec<ProgramAccount> {
+        let response: RpcResponse<Vec<ProgramAccount>> =
+            serde_json::from_str(include_str!("../fixtures/get_program_accounts_stake.json"))
+                .unwrap();
+        response.result.unwrap()
+    }
+
+    #[test]
+    fn decodes_delegated_and_initialized_accounts() {
+        let found = found();
+        let active = StakeAccount::decode(&found[0].pubkey, &found[0].account).unwrap();
+        assert_eq!(active.rent_exempt_reserve, 2_282_880);
+        assert_eq!(
+            active.delegation,
+            Some(Delegation {
+                stake: 100 * SOL,
+                activation_epoch: 700,
+                deactivation_epoch: NEVER,
+            })
+        );
+        let initialized = StakeAccount::decode(&found[3].pubkey, &found[3].account).unwrap();
+        assert_eq!(initialized.delegation, None);
+        assert_eq!(initialized.amount(730), 5 * SOL);
+
+        let mut not_stake = found[0].account.clone();
+        not_stake.owner = "11111111111111111111111111111111".to_string();
+        assert!(StakeAccount::decode("x", &not_stake).is_err());
+        let mut short = found[0].account.clone();
+        short.data.0 = "AgAAAA==".to_string();
+        assert_eq!(
+            StakeAccount::decode("x", &short).unwrap_err().to_string(),
+            "the stake account is too short"
+        );
+    }
+
+    #[test]
+    fn activation_follows_the_epochs() {
+        let account = |activation_epoch, deactivation_epoch| StakeAccount {
+            pubkey: String::new(),
+            lamports: 2 * SOL,
+            rent_exempt_reserve: SOL / 2,
+            delegation: Some(Delegation {
+                stake: SOL,
+                activation_epoch,
+                deactivation_epoch,
+            }),
+        };
+        assert_eq!(account(730, NEVER).state(730), StakeState::Activating);
+        assert_eq!(account(730, NEVER).state(731), StakeState::Active);
+        assert_eq!(account(700, 731).state(731), StakeState::Deactivating);
+        assert_eq!(account(700, 731).state(732), StakeState::Inactive);
+        assert_eq!(account(730, 730).state(730), StakeState::Inactive);
+        assert_eq!(account(700, 720).amount(730), 3 * SOL / 2);
+        assert_eq!(account(700, NEVER).amount(730), SOL);
+    }
+
+    #[test]
+    fn totals_count_each_account_once() {
+        // the wallet is both staker and withdrawer, so both filters match
+        let stake = StakeAccounts::new(730, vec![found(), found()]).unwrap();
+        assert_eq!(stake.accounts.len(), 4);
+        assert_eq!(
+            stake.totals(),
+            StakeTotals {
+                active: 100 * SOL,
+                activating: 25 * SOL,
+                deactivating: 10 * SOL,
+                inactive: 5 * SOL,
+            }
+        );
+        assert_eq!(stake.totals().total(), 140 * SOL);
+    }
+}
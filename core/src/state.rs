@@ -0,0 +1,256 @@
+use core::fmt::Display;
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    currency::Currency,
+    history::{RingBuffer, PRICE_HISTORY_LEN},
+    leader::LeaderSchedule,
+    lst::{LstHolding, LstRate},
+    production::ProductionHistory,
+    rewards::RewardHistory,
+    rpc::EpochInfo,
+    stake::StakeAccounts,
+    tokens::{TokenBalance, TokenPrice},
+    validator::ValidatorStatus,
+};
+
+/// A fetched value together with when it was fetched and why the last fetch
+/// failed, so screens can tell a real zero from a dead endpoint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metric<T> {
+    /// Last good value, kept when later fetches fail.
+    pub value: Option<T>,
+    /// When `value` was fetched.
+    pub fetched_at: Option<Instant>,
+    /// Error of the last fetch, cleared by the next successful one.
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freshness {
+    Fresh,
+    /// The value is older than allowed or the last refresh failed.
+    Stale(Duration),
+    /// Nothing was fetched successfully yet.
+    Missing,
+}
+
+impl<T> Default for Metric<T> {
+    fn default() -> Self {
+        Self {
+            value: None,
+            fetched_at: None,
+            last_error: None,
+        }
+    }
+}
+
+impl<T> Metric<T> {
+    pub fn record<E: Display>(&mut self, result: Result<T, E>, now: Instant) {
+        match result {
+            Ok(value) => {
+                self.value = Some(value);
+                self.fetched_at = Some(now);
+                self.last_error = None;
+            }
+            Err(err) => self.record_error(err),
+        }
+    }
+
+    /// Keep the last good value but remember why refreshing it failed.
+    pub fn record_error<E: Display>(&mut self, err: E) {
+        self.last_error = Some(err.to_string());
+    }
+
+    pub fn age(&self, now: Instant) -> Option<Duration> {
+        self.fetched_at
+            .map(|fetched_at| now.saturating_duration_since(fetched_at))
+    }
+
+    pub fn freshness(&self, now: Instant, max_age: Duration) -> Freshness {
+        match (&self.value, self.age(now)) {
+            (Some(_), Some(age)) if age > max_age || self.last_error.is_some() => {
+                Freshness::Stale(age)
+            }
+            (Some(_), Some(_)) => Freshness::Fresh,
+            _ => Freshness::Missing,
+        }
+    }
+}
+
+impl Freshness {
+    /// `Stale` with the largest age when any of `freshness` is stale, else
+    /// `Fresh`. Missing values are left to the page to show as missing.
+    pub fn stalest(freshness: impl IntoIterator<Item = Freshness>) -> Freshness {
+        freshness
+            .into_iter()
+            .filter_map(|freshness| match freshness {
+                Freshness::Stale(age) => Some(age),
+                _ => None,
+            })
+            .max()
+            .map_or(Freshness::Fresh, Freshness::Stale)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Performance {
+    pub slot: u64,
+    /// Every transaction per second, votes included.
+    pub tps: u64,
+    /// Transactions per second without votes, when the node reports them.
+    pub non_vote_tps: Option<u64>,
+    /// Average time per slot over the samples.
+    pub slot_time: Option<Duration>,
+}
+
+/// SOL price, with the oracle's confidence when it was read from an on-chain
+/// feed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SolPrice {
+    pub price: f64,
+    /// Change over the last 24 hours in percent, when the provider reports it.
+    pub change_24h: Option<f64>,
+    pub oracle: Option<OracleQuote>,
+}
+
+impl From<f64> for SolPrice {
+    fn from(price: f64) -> Self {
+        Self {
+            price,
+            change_24h: None,
+            oracle: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OracleQuote {
+    /// Confidence interval around the price, in the same unit.
+    pub confidence: f64,
+    /// Unix time the oracle published the price.
+    pub publish_time: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Clock {
+    pub time: String,
+    pub date: String,
+}
+
+/// Latest value of every data source, as published by the fetcher.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Snapshot {
+    /// One balance per configured wallet, in config order.
+    pub balances: Vec<Metric<u64>>,
+    pub performance: Metric<Performance>,
+    /// Currency the SOL and token prices are quoted in.
+    pub quote: Currency,
+    pub price: Metric<SolPrice>,
+    /// SOL price after every successful fetch, for the sparkline.
+    pub price_history: RingBuffer<f64, PRICE_HISTORY_LEN>,
+    /// Allowlisted SPL token holdings summed over every wallet.
+    pub tokens: Metric<Vec<TokenBalance>>,
+    /// Prices of the held token mints.
+    pub token_prices: Metric<Vec<TokenPrice>>,
+    /// Liquid staking tokens of each wallet, allowlisted or not.
+    pub lst_holdings: Metric<Vec<LstHolding>>,
+    /// SOL each liquid staking token is worth.
+    pub lst_rates: Metric<Vec<LstRate>>,
+    pub clock: Metric<Clock>,
+    pub epoch: Metric<EpochInfo>,
+    /// The validator of the configured vote account.
+    pub validator: Metric<ValidatorStatus>,
+    /// Leader slots of that validator in the current epoch.
+    pub leader: Metric<LeaderSchedule>,
+    /// Block production of that validator over the last few epochs.
+    pub block_production: Metric<ProductionHistory>,
+    /// Stake accounts of every watched wallet.
+    pub stake: Metric<StakeAccounts>,
+    /// Rewards of those stake accounts over the last few epochs.
+    pub rewards: Metric<RewardHistory>,
+}
+
+impl Snapshot {
+    /// `(time, date)` for the footer, blank until the clock was fetched once.
+    pub fn footer(&self) -> (&str, &str) {
+        match &self.clock.value {
+            Some(clock) => (&clock.time, &clock.date),
+            None => ("", ""),
+        }
+    }
+}
+
+/// Shared between the fetcher thread, which writes it, and the render loop,
+/// which only ever copies the latest [`Snapshot`] out of it.
+#[derive(Debug, Clone, Default)]
+pub struct StateStore {
+    inner: Arc<Mutex<Snapshot>>,
+}
+
+impl StateStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        self.inner
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Apply `f` to the shared snapshot. The lock is held while `f` runs, so
+    /// keep network calls out of it.
+    pub fn update(&self, f: impl FnOnce(&mut Snapshot)) {
+        f(&mut self.inner.lock().unwrap_or_else(PoisonError::into_inner));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_fetch_keeps_the_last_good_value() {
+        let start = Instant::now();
+        let max_age = Duration::from_secs(60);
+        let mut metric = Metric::default();
+        assert_eq!(metric.freshness(start, max_age), Freshness::Missing);
+
+        metric.record(Err::<u64, _>("connection reset"), start);
+        assert_eq!(metric.freshness(start, max_age), Freshness::Missing);
+        assert_eq!(metric.last_error.as_deref(), Some("connection reset"));
+
+        metric.record(Ok::<_, String>(42), start);
+        assert_eq!(metric.freshness(start, max_age), Freshness::Fresh);
+        assert_eq!(metric.last_error, None);
+
+        let later = start + Duration::from_secs(10);
+        metric.record(Err::<u64, _>("HTTP Error: Status code 429"), later);
+        assert_eq!(metric.value, Some(42));
+        assert_eq!(
+            metric.freshness(later, max_age),
+            Freshness::Stale(Duration::from_secs(10))
+        );
+    }
+
+    #[test]
+    fn old_values_go_stale() {
+        let start = Instant::now();
+        let mut metric = Metric::default();
+        metric.record(Ok::<_, String>(1.5), start);
+        let max_age = Duration::from_secs(60);
+        assert_eq!(
+            metric.freshness(start + Duration::from_secs(60), max_age),
+            Freshness::Fresh
+        );
+        assert_eq!(
+            metric.freshness(start + Duration::from_secs(61), max_age),
+            Freshness::Stale(Duration::from_secs(61))
+        );
+    }
+}
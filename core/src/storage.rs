@@ -0,0 +1,40 @@
+use std::{cell::RefCell, collections::HashMap, error::Error, rc::Rc};
+
+/// Small values that outlive a restart, kept in flash on the device. Keys are
+/// at most 15 characters, the limit of an NVS key.
+pub trait Storage {
+    /// The value saved under `key`, `None` when nothing was saved yet.
+    fn load(&mut self, key: &str) -> Result<Option<Vec<u8>>, Box<dyn Error>>;
+
+    fn save(&mut self, key: &str, value: &[u8]) -> Result<(), Box<dyn Error>>;
+}
+
+/// [`Storage`] that forgets everything on restart, for the host and tests.
+/// Clones share their values, so a test can look at what was saved.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage {
+    values: Rc<RefCell<HashMap<String, Vec<u8>>>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<Vec<u8>> {
+        self.values.borrow().get(key).cloned()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn load(&mut self, key: &str) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        Ok(self.get(key))
+    }
+
+    fn save(&mut self, key: &str, value: &[u8]) -> Result<(), Box<dyn Error>> {
+        self.values
+            .borrow_mut()
+            .insert(key.to_string(), value.to_vec());
+        Ok(())
+    }
+}
@@ -0,0 +1,273 @@
+//! SPL token balances and the `tokens` setting, the allowlist of mints shown
+//! on the token page.
+//!
+//! The setting is a comma separated list of `symbol=mint` entries, e.g.
+//! `"USDC=EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"`. Token accounts of
+//! mints outside the list are ignored; an empty list shows every mint held.
+
+use core::fmt;
+use std::error::Error;
+
+use crate::{
+    playlist::{find, trim},
+    rpc::TokenAccount,
+    wallets::is_address,
+};
+
+pub const TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+pub const TOKEN_2022_PROGRAM_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+
+/// Both token programs, a wallet can hold accounts of either.
+pub const TOKEN_PROGRAM_IDS: [&str; 2] = [TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID];
+
+/// Longest symbol that still leaves room for the amount on a token row.
+pub const MAX_SYMBOL_LEN: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokensError {
+    EmptyEntry,
+    MissingMint,
+    EmptySymbol,
+    SymbolTooLong,
+    InvalidMint,
+}
+
+impl TokensError {
+    pub const fn message(&self) -> &'static str {
+        match self {
+            TokensError::EmptyEntry => "the tokens list has an empty entry (stray comma?)",
+            TokensError::MissingMint => "a token entry is not written as symbol=mint",
+            TokensError::EmptySymbol => "a token has an empty symbol",
+            TokensError::SymbolTooLong => "a token symbol is longer than 6 characters",
+            TokensError::InvalidMint => "a token mint is not a base58 public key",
+        }
+    }
+}
+
+impl fmt::Display for TokensError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl Error for TokensError {}
+
+/// An allowlisted mint and the symbol it is shown with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub symbol: String,
+    pub mint: String,
+}
+
+impl Token {
+    pub fn new(symbol: &str, mint: &str) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            mint: mint.to_string(),
+        }
+    }
+}
+
+/// Split one `symbol=mint` entry into `(symbol, mint)`.
+const fn parse_entry(entry: &[u8]) -> Result<(&[u8], &[u8]), TokensError> {
+    let entry = trim(entry);
+    if entry.is_empty() {
+        return Err(TokensError::EmptyEntry);
+    }
+    let (symbol, mint) = match entry.split_at(find(entry, b'=')) {
+        (_, []) => return Err(TokensError::MissingMint),
+        (symbol, [_, mint @ ..]) => (trim(symbol), trim(mint)),
+    };
+    if symbol.is_empty() {
+        return Err(TokensError::EmptySymbol);
+    }
+    if symbol.len() > MAX_SYMBOL_LEN {
+        return Err(TokensError::SymbolTooLong);
+    }
+    if !is_address(mint) {
+        return Err(TokensError::InvalidMint);
+    }
+    Ok((symbol, mint))
+}
+
+/// Validate the tokens setting without allocating, usable in `const` context.
+/// An empty setting is valid.
+pub const fn validate(tokens: &str) -> Result<(), TokensError> {
+    let mut rest = tokens.as_bytes();
+    if trim(rest).is_empty() {
+        return Ok(());
+    }
+    loop {
+        let (entry, tail) = rest.split_at(find(rest, b','));
+        if let Err(err) = parse_entry(entry) {
+            return Err(err);
+        }
+        match tail {
+            [] => return Ok(()),
+            [_, tail @ ..] => rest = tail,
+        }
+    }
+}
+
+/// Panics with a readable message on an invalid setting. Meant for
+/// `const _: () = tokens::check(CONFIG.tokens);` so it fails the build.
+pub const fn check(tokens: &str) {
+    if let Err(err) = validate(tokens) {
+        panic!("{}", err.message());
+    }
+}
+
+pub fn parse(tokens: &str) -> Result<Vec<Token>, TokensError> {
+    validate(tokens)?;
+    if tokens.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    tokens
+        .split(',')
+        .map(|entry| {
+            let (symbol, mint) = parse_entry(entry.as_bytes())?;
+            let text = |bytes| String::from_utf8_lossy(bytes).into_owned();
+            Ok(Token {
+                symbol: text(symbol),
+                mint: text(mint),
+            })
+        })
+        .collect()
+}
+
+/// Holdings of one mint summed over every token account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenBalance {
+    pub symbol: String,
+    pub mint: String,
+    /// Raw amount in base units.
+    pub amount: u64,
+    pub decimals: u8,
+}
+
+impl TokenBalance {
+    /// The amount with the mint's decimals applied.
+    pub fn ui_amount(&self) -> f64 {
+        self.amount as f64 / 10f64.powi(self.decimals as i32)
+    }
+}
+
+/// Price of one mint in the configured quote currency.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenPrice {
+    pub mint: String,
+    pub price: f64,
+}
+
+/// Shortened mint used as the symbol of a token that is not allowlisted.
+fn short_mint(mint: &str) -> String {
+    mint.chars().take(4).collect()
+}
+
+/// Sum `accounts` per mint. With an allowlist the result has one entry per
+/// allowlisted token, in config order and zero when none is held; without
+/// one every mint with a non-zero amount is listed.
+pub fn token_balances(
+    allowlist: &[Token],
+    accounts: &[TokenAccount],
+) -> Result<Vec<TokenBalance>, Box<dyn Error>> {
+    let mut balances: Vec<TokenBalance> = allowlist
+        .iter()
+        .map(|token| TokenBalance {
+            symbol: token.symbol.clone(),
+            mint: token.mint.clone(),
+            amount: 0,
+            decimals: 0,
+        })
+        .collect();
+
+    for account in accounts {
+        let info = &account.account.data.parsed.info;
+        let amount: u64 = info.token_amount.amount.parse().map_err(|_| {
+            format!(
+                "invalid amount {:?} in token account {}",
+                info.token_amount.amount, account.pubkey
+            )
+        })?;
+        let position = balances.iter().position(|b| b.mint == info.mint);
+        let balance = match position {
+            Some(i) => &mut balances[i],
+            None if allowlist.is_empty() && amount > 0 => {
+                balances.push(TokenBalance {
+                    symbol: short_mint(&info.mint),
+                    mint: info.mint.clone(),
+                    amount: 0,
+                    decimals: 0,
+                });
+                balances.last_mut().expect("just pushed")
+            }
+            None => continue,
+        };
+        balance.amount = balance.amount.saturating_add(amount);
+        balance.decimals = info.token_amount.decimals;
+    }
+    Ok(balances)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::{RpcResponse, WithContext};
+
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+    const PYUSD: &str = "2b1kV6DkPAnxd5ixfnxCpjxmKwqjjaYmCZfHsFu24GXo";
+
+    fn fixture_accounts() -> Vec<TokenAccount> {
+        let response: RpcResponse<WithContext<Vec<TokenAccount>>> =
+            serde_json::from_str(include_str!("../fixtures/get_token_accounts_by_owner.json"))
+                .unwrap();
+        response.result.unwrap().value
+    }
+
+    #[test]
+    fn parses_the_allowlist() {
+        assert_eq!(parse(" ").unwrap(), []);
+        assert_eq!(
+            parse(&format!("USDC={USDC}, PYUSD = {PYUSD}")).unwrap(),
+            [Token::new("USDC", USDC), Token::new("PYUSD", PYUSD)]
+        );
+        assert_eq!(validate("USDC"), Err(TokensError::MissingMint));
+        assert_eq!(validate(&format!("={USDC}")), Err(TokensError::EmptySymbol));
+        assert_eq!(
+            validate(&format!("USDCOIN={USDC}")),
+            Err(TokensError::SymbolTooLong)
+        );
+        assert_eq!(validate("USDC=nope"), Err(TokensError::InvalidMint));
+        assert_eq!(
+            validate(&format!("USDC={USDC},")),
+            Err(TokensError::EmptyEntry)
+        );
+    }
+
+    #[test]
+    fn balances_follow_the_allowlist_and_apply_decimals() {
+        let allowlist = [
+            Token::new("PYUSD", PYUSD),
+            Token::new("USDC", USDC),
+            Token::new("BONK", "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263"),
+        ];
+        let balances = token_balances(&allowlist, &fixture_accounts()).unwrap();
+        let summary: Vec<_> = balances
+            .iter()
+            .map(|b| (b.symbol.as_str(), b.ui_amount()))
+            .collect();
+        // two USDC accounts are summed, BONK is not held
+        assert_eq!(
+            summary,
+            [("PYUSD", 25.5), ("USDC", 1_250.75), ("BONK", 0.0)]
+        );
+    }
+
+    #[test]
+    fn without_allowlist_every_held_mint_is_listed() {
+        let balances = token_balances(&[], &fixture_accounts()).unwrap();
+        let symbols: Vec<_> = balances.iter().map(|b| b.symbol.as_str()).collect();
+        // the empty account of the unknown mint is skipped
+        assert_eq!(symbols, ["EPjF", "2b1k"]);
+    }
+}
@@ -0,0 +1,152 @@
+//! The `vote_account` setting: a validator the device keeps an eye on.
+//!
+//! `getVoteAccounts` filtered to the configured vote account gives its stake,
+//! commission and last vote, and whether the cluster considers it delinquent.
+//! The skip rate is the one of the current epoch in the block production
+//! history, fetched for the identity the vote account names. An empty setting
+//! turns the validator page and the delinquency alert off.
+//!
+//! Like the playlist, [`check`] is a `const fn` so a malformed vote account in
+//! `cfg.toml` fails the build.
+
+use core::fmt;
+use std::error::Error;
+
+use crate::{playlist::trim, rpc::VoteAccounts, wallets::is_address};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidatorStatus {
+    pub vote_pubkey: String,
+    /// Identity that produces the blocks of the validator.
+    pub node_pubkey: String,
+    /// In lamports.
+    pub activated_stake: u64,
+    pub commission: u8,
+    pub last_vote: u64,
+    /// Slot the cluster was at when the vote accounts were fetched.
+    pub slot: u64,
+    pub delinquent: bool,
+}
+
+impl ValidatorStatus {
+    /// Slots since the last vote landed.
+    pub fn vote_lag(&self) -> u64 {
+        self.slot.saturating_sub(self.last_vote)
+    }
+}
+
+/// Status of `vote_pubkey` from the `getVoteAccounts` result and the current
+/// `slot`.
+pub fn validator_status(
+    vote_pubkey: &str,
+    accounts: VoteAccounts,
+    slot: u64,
+) -> Result<ValidatorStatus, Box<dyn Error>> {
+    let current = accounts.current.into_iter().map(|account| (account, false));
+    let delinquent = accounts
+        .delinquent
+        .into_iter()
+        .map(|account| (account, true));
+    let (account, delinquent) = current
+        .chain(delinquent)
+        .find(|(account, _)| account.vote_pubkey == vote_pubkey)
+        .ok_or_else(|| format!("vote account {} not found", vote_pubkey))?;
+    Ok(ValidatorStatus {
+        vote_pubkey: account.vote_pubkey,
+        node_pubkey: account.node_pubkey,
+        activated_stake: account.activated_stake,
+        commission: account.commission,
+        last_vote: account.last_vote,
+        slot,
+        delinquent,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidatorError {
+    InvalidVoteAccount,
+}
+
+impl ValidatorError {
+    pub const fn message(&self) -> &'static str {
+        match self {
+            ValidatorError::InvalidVoteAccount => "the vote account is not a base58 public key",
+        }
+    }
+}
+
+impl fmt::Display for ValidatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl Error for ValidatorError {}
+
+/// Empty, or a public key.
+pub const fn validate(vote_account: &str) -> Result<(), ValidatorError> {
+    let vote_account = trim(vote_account.as_bytes());
+    if vote_account.is_empty() || is_address(vote_account) {
+        Ok(())
+    } else {
+        Err(ValidatorError::InvalidVoteAccount)
+    }
+}
+
+/// Panics with a readable message on an invalid vote account. Meant for
+/// `const _: () = validator::check(CONFIG.vote_account);` so it fails the build.
+pub const fn check(vote_account: &str) {
+    if let Err(err) = validate(vote_account) {
+        panic!("{}", err.message());
+    }
+}
+
+/// The vote account to watch, `None` when the setting is empty.
+pub fn parse(vote_account: &str) -> Result<Option<String>, ValidatorError> {
+    validate(vote_account)?;
+    let vote_account = vote_account.trim();
+    Ok((!vote_account.is_empty()).then(|| vote_account.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::{SAMPLE_VOTE_ACCOUNT, SAMPLE_VOTE_IDENTITY};
+
+    fn vote_accounts() -> VoteAccounts {
+        let response: serde_json::Value =
+            serde_json::from_str(include_str!("../fixtures/get_vote_accounts.json")).unwrap();
+        serde_json::from_value(response["result"].clone()).unwrap()
+    }
+
+    #[test]
+    fn status_of_a_voting_validator() {
+        let status = validator_status(SAMPLE_VOTE_ACCOUNT, vote_accounts(), 315_482_100).unwrap();
+        assert_eq!(status.node_pubkey, SAMPLE_VOTE_IDENTITY);
+        assert_eq!(status.activated_stake, 412_345_678_901_234);
+        assert_eq!(status.commission, 5);
+        assert_eq!(status.vote_lag(), 3);
+        assert!(!status.delinquent);
+    }
+
+    #[test]
+    fn delinquent_and_missing_vote_accounts() {
+        let mut accounts = vote_accounts();
+        accounts.delinquent = std::mem::take(&mut accounts.current);
+        let status = validator_status(SAMPLE_VOTE_ACCOUNT, accounts, 315_482_100).unwrap();
+        assert!(status.delinquent);
+
+        let err = validator_status("Vote111", vote_accounts(), 1).unwrap_err();
+        assert_eq!(err.to_string(), "vote account Vote111 not found");
+    }
+
+    #[test]
+    fn vote_account_setting() {
+        assert_eq!(parse(""), Ok(None));
+        assert_eq!(
+            parse(&format!(" {} ", SAMPLE_VOTE_ACCOUNT)),
+            Ok(Some(SAMPLE_VOTE_ACCOUNT.to_string()))
+        );
+        assert_eq!(parse("vote"), Err(ValidatorError::InvalidVoteAccount));
+    }
+}
@@ -0,0 +1,210 @@
+//! The `wallets` setting: the labeled wallets the device watches.
+//!
+//! Written as a comma separated list of `label=address` entries, e.g.
+//! `"Treasury=aKgf..., Hot=9WzD..."`. A bare address is accepted too and gets
+//! the label `Wallet`, so a single wallet config stays a one-liner.
+//!
+//! Like the playlist, [`check`] is a `const fn` so a malformed entry in
+//! `cfg.toml` fails the build.
+
+use core::fmt;
+
+use crate::playlist::{eq, find, trim};
+
+/// Label of an entry that only has an address.
+pub const DEFAULT_LABEL: &str = "Wallet";
+
+/// Longest label that still fits a page title on the 128px panel.
+pub const MAX_LABEL_LEN: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalletsError {
+    Empty,
+    EmptyEntry,
+    EmptyLabel,
+    LabelTooLong,
+    InvalidAddress,
+}
+
+impl WalletsError {
+    pub const fn message(&self) -> &'static str {
+        match self {
+            WalletsError::Empty => "no wallet is configured",
+            WalletsError::EmptyEntry => "the wallets list has an empty entry (stray comma?)",
+            WalletsError::EmptyLabel => "a wallet has an empty label",
+            WalletsError::LabelTooLong => "a wallet label is longer than 12 characters",
+            WalletsError::InvalidAddress => "a wallet address is not a base58 public key",
+        }
+    }
+}
+
+impl fmt::Display for WalletsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for WalletsError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wallet {
+    pub label: String,
+    pub address: String,
+}
+
+impl Wallet {
+    pub fn new(label: &str, address: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            address: address.to_string(),
+        }
+    }
+}
+
+/// A base58 encoded 32 byte public key is 32 to 44 characters long.
+pub(crate) const fn is_address(address: &[u8]) -> bool {
+    if address.len() < 32 || address.len() > 44 {
+        return false;
+    }
+    let mut i = 0;
+    while i < address.len() {
+        match address[i] {
+            b'0' | b'O' | b'I' | b'l' => return false,
+            byte if byte.is_ascii_alphanumeric() => {}
+            _ => return false,
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Split one `[label=]address` entry into `(label, address)`.
+const fn parse_entry(entry: &[u8]) -> Result<(&[u8], &[u8]), WalletsError> {
+    let entry = trim(entry);
+    if entry.is_empty() {
+        return Err(WalletsError::EmptyEntry);
+    }
+    let (label, address) = match entry.split_at(find(entry, b'=')) {
+        (address, []) => (DEFAULT_LABEL.as_bytes(), address),
+        (label, [_, address @ ..]) => (trim(label), trim(address)),
+    };
+    if label.is_empty() {
+        return Err(WalletsError::EmptyLabel);
+    }
+    if label.len() > MAX_LABEL_LEN {
+        return Err(WalletsError::LabelTooLong);
+    }
+    if !is_address(address) {
+        return Err(WalletsError::InvalidAddress);
+    }
+    Ok((label, address))
+}
+
+/// Position of the wallet labeled `label` in a valid wallets setting.
+pub(crate) const fn label_index(wallets: &str, label: &[u8]) -> Option<usize> {
+    let mut rest = wallets.as_bytes();
+    let mut index = 0;
+    loop {
+        let (entry, tail) = rest.split_at(find(rest, b','));
+        if let Ok((entry_label, _)) = parse_entry(entry) {
+            if eq(entry_label, label) {
+                return Some(index);
+            }
+        }
+        match tail {
+            [] => return None,
+            [_, tail @ ..] => rest = tail,
+        }
+        index += 1;
+    }
+}
+
+/// Validate the wallets setting without allocating, usable in `const` context.
+pub const fn validate(wallets: &str) -> Result<(), WalletsError> {
+    let mut rest = wallets.as_bytes();
+    if trim(rest).is_empty() {
+        return Err(WalletsError::Empty);
+    }
+    loop {
+        let (entry, tail) = rest.split_at(find(rest, b','));
+        if let Err(err) = parse_entry(entry) {
+            return Err(err);
+        }
+        match tail {
+            [] => return Ok(()),
+            [_, tail @ ..] => rest = tail,
+        }
+    }
+}
+
+/// Panics with a readable message on an invalid setting. Meant for
+/// `const _: () = wallets::check(CONFIG.wallets);` so it fails the build.
+pub const fn check(wallets: &str) {
+    if let Err(err) = validate(wallets) {
+        panic!("{}", err.message());
+    }
+}
+
+pub fn parse(wallets: &str) -> Result<Vec<Wallet>, WalletsError> {
+    validate(wallets)?;
+    wallets
+        .split(',')
+        .map(|entry| {
+            let (label, address) = parse_entry(entry.as_bytes())?;
+            // both halves are sub-slices of a `&str` split on ASCII bytes
+            let text = |bytes| String::from_utf8_lossy(bytes).into_owned();
+            Ok(Wallet {
+                label: text(label),
+                address: text(address),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TREASURY: &str = "aKgfWjGePnbFgDAuCqxB5oymuFxQskvCtrw6eYfDa7fg";
+    const HOT: &str = "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM";
+
+    #[test]
+    fn parses_labeled_and_bare_addresses() {
+        let wallets = parse(&format!(" Treasury = {TREASURY},{HOT} ")).unwrap();
+        assert_eq!(
+            wallets,
+            [
+                Wallet::new("Treasury", TREASURY),
+                Wallet::new("Wallet", HOT)
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_bad_wallets() {
+        assert_eq!(validate(" "), Err(WalletsError::Empty));
+        assert_eq!(
+            validate(&format!("{TREASURY},")),
+            Err(WalletsError::EmptyEntry)
+        );
+        assert_eq!(
+            validate(&format!("={TREASURY}")),
+            Err(WalletsError::EmptyLabel)
+        );
+        assert_eq!(
+            validate(&format!("Operations fund={TREASURY}")),
+            Err(WalletsError::LabelTooLong)
+        );
+        assert_eq!(validate("Hot=abc"), Err(WalletsError::InvalidAddress));
+        assert_eq!(
+            validate(&format!("Hot={}0", &HOT[1..])),
+            Err(WalletsError::InvalidAddress)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "base58")]
+    fn check_panics_with_the_error_message() {
+        check("Treasury=not an address");
+    }
+}
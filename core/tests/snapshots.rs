@@ -0,0 +1,48 @@
+//! Golden-image tests for the screen layouts.
+//!
+//! A mismatch writes `<screen>.actual.png` next to the golden file, and so
+//! does a screen without a golden file. Run with `UPDATE_SNAPSHOTS=1` to
+//! accept the new layouts.
+
+use std::{env, fs, path::PathBuf};
+
+use solana_display_core::{framebuffer::Framebuffer, simulator::render_screens};
+
+#[test]
+fn screens_match_golden_images() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots");
+    let update = env::var_os("UPDATE_SNAPSHOTS").is_some();
+    let mut failures = Vec::new();
+
+    for (name, actual) in render_screens() {
+        let golden_path = dir.join(format!("{}.png", name));
+        let actual_path = dir.join(format!("{}.actual.png", name));
+        let png = actual.to_png(1).unwrap();
+
+        if update {
+            fs::write(&golden_path, &png).unwrap();
+            let _ = fs::remove_file(&actual_path);
+            continue;
+        }
+        if !golden_path.exists() {
+            fs::write(&actual_path, &png).unwrap();
+            failures.push(format!("{}: no golden image", name));
+            continue;
+        }
+
+        let golden = Framebuffer::from_png(&fs::read(&golden_path).unwrap()).unwrap();
+        let diff = golden.diff(&actual);
+        if diff == 0 {
+            let _ = fs::remove_file(&actual_path);
+        } else {
+            fs::write(&actual_path, &png).unwrap();
+            failures.push(format!("{}: {} pixels differ", name, diff));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "layout changed:\n{}\nrerun with UPDATE_SNAPSHOTS=1 to accept the new layouts",
+        failures.join("\n")
+    );
+}
@@ -3,10 +3,9 @@ use std::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use display::DisplayModule;
 use embedded_graphics::mono_font::ascii::FONT_6X10;
 use esp_idf_hal::{
     gpio::{PinDriver, Pull},
@@ -14,11 +13,24 @@ use esp_idf_hal::{
     sys::{esp_err_to_name, nvs_flash_init, ESP_OK},
 };
 use esp_idf_svc::sntp::EspSntp;
-use http::Http;
+use http::EspTransport;
+use solana_display_core::{
+    alerts::{self, alert_leds, Alert, AlertMonitor, ALERT_BLINK},
+    currency,
+    display::DisplayModule,
+    fetcher::Fetcher,
+    http::Http,
+    playlist, price,
+    screen::Rotation,
+    state::StateStore,
+    tokens, validator, wallets,
+};
+use storage::NvsStorage;
 use wifi::wifi;
 
 mod display;
 mod http;
+mod storage;
 mod wifi;
 
 #[toml_cfg::toml_config]
@@ -30,9 +42,38 @@ pub struct Config {
     #[default("")]
     sol_rpc: &'static str,
     #[default("")]
-    wallet_address: &'static str,
+    wallets: &'static str,
+    #[default("balance=1500, slots=1500, price=1500, qr=6000")]
+    playlist: &'static str,
+    #[default(5)]
+    tps_samples: u32,
+    #[default("")]
+    tokens: &'static str,
+    #[default("coingecko, jupiter, pyth")]
+    price_providers: &'static str,
+    #[default("usd")]
+    quote_currency: &'static str,
+    #[default("")]
+    alerts: &'static str,
+    #[default("")]
+    vote_account: &'static str,
 }
 
+// a typo'd screen name, wallet address or token mint in cfg.toml fails the build
+const _: () = playlist::check(CONFIG.playlist);
+const _: () = wallets::check(CONFIG.wallets);
+const _: () = tokens::check(CONFIG.tokens);
+const _: () = price::check(CONFIG.price_providers);
+const _: () = currency::check(CONFIG.quote_currency);
+const _: () = price::check_quote(CONFIG.price_providers, CONFIG.quote_currency);
+const _: () = alerts::check(CONFIG.alerts, CONFIG.wallets);
+const _: () = validator::check(CONFIG.vote_account);
+// getRecentPerformanceSamples returns at most 720 samples
+const _: () = assert!(
+    CONFIG.tps_samples >= 1 && CONFIG.tps_samples <= 720,
+    "tps_samples must be between 1 and 720"
+);
+
 fn main() {
     // It is necessary to call this function once. Otherwise some patches to the runtime
     // implemented by esp-idf-sys might not link properly. See https://github.com/esp-rs/esp-idf-template/issues/71
@@ -64,10 +105,18 @@ fn main() {
 
     let is_on = Arc::new(AtomicBool::new(true));
     let is_on_clone = Arc::clone(&is_on);
-    let mut display_module = DisplayModule::init(i2c, sda, scl, &app_config.wallet_address);
+    // while an alert is up the button clears it instead of toggling the display
+    let alert_up = Arc::new(AtomicBool::new(false));
+    let alert_up_clone = Arc::clone(&alert_up);
+    let alert_cleared = Arc::new(AtomicBool::new(false));
+    let alert_cleared_clone = Arc::clone(&alert_cleared);
+    let mut display_module = DisplayModule::new(display::init(i2c, sda, scl));
 
     std::thread::spawn(move || loop {
-        if button.is_low() {
+        if button.is_low() && alert_up_clone.load(Ordering::SeqCst) {
+            alert_cleared_clone.store(true, Ordering::SeqCst);
+            println!("Button pressed. Alert cleared");
+        } else if button.is_low() {
             is_on_clone.store(!is_on_clone.load(Ordering::SeqCst), Ordering::SeqCst);
             println!(
                 "Button toggled. is_on: {}",
@@ -95,7 +144,52 @@ fn main() {
         app_config.wifi_psk,
     );
 
-    let mut http = Http::init(&app_config.sol_rpc).expect("Http module initialization failed");
+    let wallets =
+        wallets::parse(app_config.wallets).expect("the wallets are checked at build time");
+    let tokens = tokens::parse(app_config.tokens).expect("the tokens are checked at build time");
+    let quote = currency::find(app_config.quote_currency)
+        .expect("the quote currency is checked at build time");
+    let mut rotation = Rotation::from_playlist(app_config.playlist, &wallets)
+        .expect("the playlist is checked at build time");
+    let vote_account = validator::parse(app_config.vote_account)
+        .expect("the vote account is checked at build time");
+    let mut alerts =
+        alerts::parse(app_config.alerts, &wallets).expect("the alerts are checked at build time");
+    if vote_account.is_some() {
+        alerts.push(Alert::delinquency());
+    }
+    let mut monitor = AlertMonitor::new(alerts);
+
+    // the fetcher owns the network, the loop below only reads its latest results
+    let store = StateStore::new();
+    let fetcher_store = store.clone();
+    let mut sources = rotation.sources();
+    for source in monitor.sources() {
+        if !sources.contains(&source) {
+            sources.push(source);
+        }
+    }
+    std::thread::Builder::new()
+        .stack_size(16 * 1024) // TLS handshakes need more than the default pthread stack
+        .spawn(move || {
+            let transport = EspTransport::init().expect("Http module initialization failed");
+            let http = Http::init(transport, app_config.sol_rpc);
+            let prices = price::PriceProviders::from_setting(app_config.price_providers)
+                .expect("the price providers are checked at build time");
+            let mut fetcher = Fetcher::new(http, fetcher_store, &wallets, &sources)
+                .with_tps_samples(app_config.tps_samples as usize)
+                .with_tokens(&tokens)
+                .with_price_providers(prices)
+                .with_quote_currency(quote)
+                .with_vote_account(vote_account);
+            // without flash the history just starts over on every restart
+            match NvsStorage::init() {
+                Ok(storage) => fetcher = fetcher.with_storage(storage),
+                Err(e) => log::error!("Error opening the NVS storage: {}", e),
+            }
+            fetcher.run();
+        })
+        .unwrap();
     display_module.create_black_rectangle();
 
     let device_ready = "Device Ready";
@@ -110,7 +204,28 @@ fn main() {
 
     led_1.set_low().unwrap();
     let mut previous_state = true;
+    let mut alert_step = 0;
     loop {
+        let snapshot = store.snapshot();
+        if monitor.check(&snapshot) {
+            if alert_cleared.swap(false, Ordering::SeqCst) {
+                monitor.acknowledge();
+                alert_up.store(false, Ordering::SeqCst);
+                // redraw the off screen if the display was off before the alert
+                previous_state = true;
+                continue;
+            }
+            alert_up.store(true, Ordering::SeqCst);
+            let [led_1_on, led_2_on, led_3_on] = alert_leds(alert_step);
+            led_1.set_level(led_1_on.into()).unwrap();
+            led_2.set_level(led_2_on.into()).unwrap();
+            led_3.set_level(led_3_on.into()).unwrap();
+            display_module.draw_alert(monitor.latched(), alert_step % 2 == 1);
+            alert_step = alert_step.wrapping_add(1);
+            std::thread::sleep(ALERT_BLINK);
+            continue;
+        }
+
         let show_data = is_on.load(Ordering::SeqCst);
         if show_data {
             led_2.set_high().unwrap();
@@ -119,7 +234,8 @@ fn main() {
                 previous_state = true;
             }
             led_3.set_low().unwrap();
-            display_module.perpetual_data(&mut http);
+            let dwell = rotation.show_next(&mut display_module, &snapshot, Instant::now());
+            std::thread::sleep(dwell);
         } else if !show_data && previous_state {
             display_module.create_black_rectangle();
             println!("Device Off");
@@ -0,0 +1,34 @@
+use esp_idf_svc::nvs::{EspDefaultNvsPartition, EspNvs, NvsDefault};
+use solana_display_core::storage::Storage;
+use std::error::Error;
+
+/// Namespace of the firmware's values in the default NVS partition.
+const NAMESPACE: &str = "solana";
+
+/// Largest value read back, the NVS blobs of the histories are well below it.
+const MAX_VALUE_LEN: usize = 1024;
+
+pub struct NvsStorage {
+    nvs: EspNvs<NvsDefault>,
+}
+
+impl NvsStorage {
+    pub fn init() -> Result<Self, Box<dyn Error>> {
+        let partition = EspDefaultNvsPartition::take()?;
+        Ok(Self {
+            nvs: EspNvs::new(partition, NAMESPACE, true)?,
+        })
+    }
+}
+
+impl Storage for NvsStorage {
+    fn load(&mut self, key: &str) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        let mut buf = vec![0; MAX_VALUE_LEN];
+        Ok(self.nvs.get_raw(key, &mut buf)?.map(<[u8]>::to_vec))
+    }
+
+    fn save(&mut self, key: &str, value: &[u8]) -> Result<(), Box<dyn Error>> {
+        self.nvs.set_raw(key, value)?;
+        Ok(())
+    }
+}